@@ -0,0 +1,561 @@
+//! `#[derive(AstNode)]`, which writes the visitor boilerplate for the syntax
+//! tree enums in `rulox`, so adding a node is a single edit to the enum.
+//!
+//! For an enum `Expr` whose visitor trait is `Visitor`, it generates:
+//!
+//! - `trait Visitor<R>`, with a `visit_<variant>_expr` method per variant
+//!   taking each field by reference (`Box<T>` as `&T`, `Vec<T>` as `&[T]`)
+//! - `Expr::accept`, which calls the method for the variant at hand
+//! - a `walk_<variant>_expr` function per variant that visits the variant's
+//!   children, for `Visitor<()>` implementations that only care about a few
+//!   kinds of node
+//!
+//! and, when asked for with `visitor_mut`:
+//!
+//! - `trait VisitorMut<R>`, like `Visitor<R>` but with methods taking
+//!   `&mut self`, for visitors such as the interpreter that update their own
+//!   state as they go
+//! - `Expr::accept_mut`
+//! - a `walk_<variant>_expr_mut` function per variant that visits the
+//!   variant's children, for `VisitorMut<()>` implementations
+//!
+//! and, when also asked for with `walker_mut`:
+//!
+//! - `trait WalkerMut`, a `VisitorMut<()>` whose methods by default call the
+//!   walk functions, so implementations override only the nodes they care
+//!   about and can call the walk function to keep recursing
+//!
+//! and, when asked for with `fold`:
+//!
+//! - `trait Fold`, which takes a tree by value and returns a rewritten one.
+//!   `fold_expr` dispatches to a `fold_<variant>_expr` method per variant,
+//!   which by default rebuilds the node from its folded children
+//! - a `walk_<variant>_expr_fold` function per variant doing that rebuild,
+//!   for overrides that want the children folded first
+//!
+//! The enum is configured with `#[ast(...)]`:
+//!
+//! - `visitor = Path` names the visitor trait, `ExprVisitor` by default
+//! - `visitor_mut = Path` names the `&mut self` visitor trait
+//! - `walker_mut = Path` names its recursing counterpart
+//! - `children(Expr = Visitor, ...)` lists other node types the enum holds
+//!   and their visitor traits, so the walk functions descend into them too
+//! - `children_mut(Expr = VisitorMut, ...)` does the same for `visitor_mut`.
+//!   The traits listed, as `VisitorMut<()>`, become supertraits of the
+//!   walker.
+//! - `fold = Path` names the fold trait
+//! - `children_fold(Expr = Fold, ...)` does the same for `fold`
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, GenericArgument, Ident, Path, PathArguments, Type,
+};
+
+#[proc_macro_derive(AstNode, attributes(ast))]
+pub fn derive_ast_node(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Options {
+    visitor: Path,
+    visitor_mut: Option<Path>,
+    walker_mut: Option<Path>,
+    /// Node types the walk functions descend into, and the visitor trait
+    /// for each. Always includes the enum itself.
+    children: Vec<(Ident, Path)>,
+    /// The same for `visitor_mut`, not including the enum itself.
+    children_mut: Vec<(Ident, Path)>,
+    fold: Option<Path>,
+    /// The same for `fold`, not including the enum itself.
+    children_fold: Vec<(Ident, Path)>,
+}
+
+/// How a field holds its value, which decides how visitors receive it.
+enum Shape<'a> {
+    Plain(&'a Type),
+    Boxed(&'a Type),
+    List(&'a Type),
+    Optional(&'a Type),
+}
+
+struct Field<'a> {
+    name: &'a Ident,
+    shape: Shape<'a>,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            input,
+            "AstNode can only be derived for enums",
+        ));
+    };
+    let options = options(input)?;
+    let name = &input.ident;
+    let visitor = &options.visitor;
+    let suffix = name.to_string().to_lowercase();
+
+    let mut methods = Vec::new();
+    let mut arms = Vec::new();
+    let mut walks = Vec::new();
+    let mut methods_mut = Vec::new();
+    let mut arms_mut = Vec::new();
+    let mut walks_mut = Vec::new();
+    let mut walker_methods = Vec::new();
+    let mut walker_impls = Vec::new();
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let fields = variant
+            .fields
+            .iter()
+            .map(|field| match &field.ident {
+                Some(name) => Ok(Field {
+                    name,
+                    shape: shape(&field.ty),
+                }),
+                None => Err(Error::new_spanned(
+                    variant,
+                    "AstNode variants need named fields",
+                )),
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        if fields.is_empty() {
+            return Err(Error::new_spanned(variant, "AstNode variants need fields"));
+        }
+
+        let variant_name = &variant.ident;
+        let visit = format_ident!("visit_{}_{}", snake_case(&variant_name.to_string()), suffix);
+        let walk = format_ident!("walk_{}_{}", snake_case(&variant_name.to_string()), suffix);
+        let names: Vec<&Ident> = fields.iter().map(|field| field.name).collect();
+        let params: Vec<TokenStream2> = fields
+            .iter()
+            .map(|field| {
+                let name = field.name;
+                let ty = param_type(&field.shape);
+                quote!(#name: #ty)
+            })
+            .collect();
+
+        methods.push(quote! {
+            fn #visit(&self, #(#params),*) -> R;
+        });
+        arms.push(quote! {
+            #name::#variant_name { #(#names),* } => visitor.#visit(#(#names),*),
+        });
+
+        let mut bounds = vec![visitor.clone()];
+        let mut visits = Vec::new();
+        for field in &fields {
+            let Some(child_visitor) = child_visitor(&options.children, &field.shape) else {
+                continue;
+            };
+            if !bounds.contains(child_visitor) {
+                bounds.push(child_visitor.clone());
+            }
+            visits.push(visit_child(field, quote!(accept::<()>)));
+        }
+        let walk_doc = format!("Visit the children of `{}::{}`.", name, variant_name);
+        walks.push(quote! {
+            #[doc = #walk_doc]
+            #[allow(dead_code, unused_variables)]
+            pub fn #walk<V: #(#bounds<()>)+*>(visitor: &V, #(#params),*) {
+                #(#visits)*
+            }
+        });
+
+        variants.push((variant_name, fields));
+        let (variant_name, fields) = variants.last().expect("just pushed");
+
+        let Some(visitor_mut) = &options.visitor_mut else {
+            continue;
+        };
+        let walk_mut = format_ident!("{}_mut", walk);
+        let visits_mut: Vec<TokenStream2> = fields
+            .iter()
+            .filter(|field| {
+                field_node(&field.shape).is_some_and(|node| {
+                    node == name || options.children_mut.iter().any(|(child, _)| child == node)
+                })
+            })
+            .map(|field| visit_child(field, quote!(accept_mut)))
+            .collect();
+        methods_mut.push(quote! {
+            fn #visit(&mut self, #(#params),*) -> R;
+        });
+        arms_mut.push(quote! {
+            #name::#variant_name { #(#names),* } => visitor.#visit(#(#names),*),
+        });
+        let bounds_mut =
+            std::iter::once(visitor_mut).chain(options.children_mut.iter().map(|(_, path)| path));
+        walks_mut.push(quote! {
+            #[doc = #walk_doc]
+            #[allow(unused_variables)]
+            pub fn #walk_mut<V: #(#bounds_mut<()>)+* + ?Sized>(visitor: &mut V, #(#params),*) {
+                #(#visits_mut)*
+            }
+        });
+
+        if let Some(walker_mut) = &options.walker_mut {
+            walker_methods.push(quote! {
+                fn #visit(&mut self, #(#params),*) {
+                    #walk_mut(self, #(#names),*)
+                }
+            });
+            walker_impls.push(quote! {
+                fn #visit(&mut self, #(#params),*) {
+                    #walker_mut::#visit(self, #(#names),*)
+                }
+            });
+        }
+    }
+
+    let visitor_mut = options.visitor_mut.as_ref().map(|visitor_mut| {
+        let trait_doc = format!(
+            "Visits each kind of `{}` with mutable access to the visitor, producing an `R`.",
+            name
+        );
+        let walker = options.walker_mut.as_ref().map(|walker_mut| {
+            let supertraits: Vec<&Path> =
+                options.children_mut.iter().map(|(_, path)| path).collect();
+            let supertraits = (!supertraits.is_empty()).then(|| quote!(: #(#supertraits<()>)+*));
+            let walker_doc = format!(
+                "A `{}<()>` whose methods recurse into the node's children unless overridden.",
+                quote!(#visitor_mut)
+            );
+            quote! {
+                #[doc = #walker_doc]
+                pub trait #walker_mut #supertraits {
+                    #(#walker_methods)*
+                }
+
+                impl<T: #walker_mut + ?Sized> #visitor_mut<()> for T {
+                    #(#walker_impls)*
+                }
+            }
+        });
+        quote! {
+            #[doc = #trait_doc]
+            pub trait #visitor_mut<R> {
+                #(#methods_mut)*
+            }
+
+            impl #name {
+                pub fn accept_mut<R, V: #visitor_mut<R> + ?Sized>(&self, visitor: &mut V) -> R {
+                    match self {
+                        #(#arms_mut)*
+                    }
+                }
+            }
+
+            #(#walks_mut)*
+
+            #walker
+        }
+    });
+
+    let fold = options
+        .fold
+        .as_ref()
+        .map(|fold| expand_fold(name, fold, &variants, &options));
+
+    let trait_doc = format!("Visits each kind of `{}`, producing an `R`.", name);
+    Ok(quote! {
+        #[doc = #trait_doc]
+        pub trait #visitor<R> {
+            #(#methods)*
+        }
+
+        impl #name {
+            pub fn accept<R>(&self, visitor: &impl #visitor<R>) -> R {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+
+        #(#walks)*
+
+        #visitor_mut
+
+        #fold
+    })
+}
+
+/// The fold trait for `name`, and the functions its default methods call.
+fn expand_fold(
+    name: &Ident,
+    fold: &Path,
+    variants: &[(&Ident, Vec<Field>)],
+    options: &Options,
+) -> TokenStream2 {
+    let suffix = name.to_string().to_lowercase();
+    let fold_node = format_ident!("fold_{}", suffix);
+
+    let mut methods = Vec::new();
+    let mut arms = Vec::new();
+    let mut walks = Vec::new();
+    for (variant_name, fields) in variants {
+        let snake = snake_case(&variant_name.to_string());
+        let method = format_ident!("fold_{}_{}", snake, suffix);
+        let walk = format_ident!("walk_{}_{}_fold", snake, suffix);
+        let names: Vec<&Ident> = fields.iter().map(|field| field.name).collect();
+        let params: Vec<TokenStream2> = fields
+            .iter()
+            .map(|field| {
+                let name = field.name;
+                let ty = field_type(&field.shape);
+                quote!(#name: #ty)
+            })
+            .collect();
+
+        let folded: Vec<TokenStream2> = fields
+            .iter()
+            .map(|field| {
+                let field_name = field.name;
+                let node = field_node(&field.shape).filter(|node| {
+                    *node == name
+                        || options
+                            .children_fold
+                            .iter()
+                            .any(|(child, _)| child == *node)
+                });
+                let Some(node) = node else {
+                    return quote!(#field_name);
+                };
+
+                let fold_child = format_ident!("fold_{}", node.to_string().to_lowercase());
+                match field.shape {
+                    Shape::Plain(_) => quote!(#field_name: folder.#fold_child(#field_name)),
+                    Shape::Boxed(_) => {
+                        quote!(#field_name: Box::new(folder.#fold_child(*#field_name)))
+                    }
+                    Shape::List(_) => quote! {
+                        #field_name: #field_name
+                            .into_iter()
+                            .map(|child| folder.#fold_child(child))
+                            .collect()
+                    },
+                    Shape::Optional(_) => {
+                        quote!(#field_name: #field_name.map(|child| folder.#fold_child(child)))
+                    }
+                }
+            })
+            .collect();
+
+        methods.push(quote! {
+            fn #method(&mut self, #(#params),*) -> #name {
+                #walk(self, #(#names),*)
+            }
+        });
+        arms.push(quote! {
+            #name::#variant_name { #(#names),* } => self.#method(#(#names),*),
+        });
+        let walk_doc = format!(
+            "Rebuild a `{}::{}` from its folded children.",
+            name, variant_name
+        );
+        walks.push(quote! {
+            #[doc = #walk_doc]
+            #[allow(dead_code, clippy::boxed_local)]
+            pub fn #walk<F: #fold + ?Sized>(folder: &mut F, #(#params),*) -> #name {
+                #name::#variant_name { #(#folded),* }
+            }
+        });
+    }
+
+    let supertraits: Vec<&Path> = options.children_fold.iter().map(|(_, path)| path).collect();
+    let supertraits = (!supertraits.is_empty()).then(|| quote!(: #(#supertraits)+*));
+    let trait_doc = format!(
+        "Rewrites a `{}`, taking it by value and returning the new tree. Every method rebuilds the node from its folded children unless overridden.",
+        name
+    );
+    quote! {
+        #[doc = #trait_doc]
+        #[allow(dead_code, clippy::boxed_local)]
+        pub trait #fold #supertraits {
+            fn #fold_node(&mut self, node: #name) -> #name {
+                match node {
+                    #(#arms)*
+                }
+            }
+
+            #(#methods)*
+        }
+
+        #(#walks)*
+    }
+}
+
+fn options(input: &DeriveInput) -> syn::Result<Options> {
+    let mut visitor: Path = format_ident!("{}Visitor", input.ident).into();
+    let mut visitor_mut = None;
+    let mut walker_mut = None;
+    let mut children = Vec::new();
+    let mut children_mut = Vec::new();
+    let mut fold = None;
+    let mut children_fold = Vec::new();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("ast"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("visitor") {
+                visitor = meta.value()?.parse()?;
+                Ok(())
+            } else if meta.path.is_ident("visitor_mut") {
+                visitor_mut = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("walker_mut") {
+                walker_mut = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("fold") {
+                fold = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("children")
+                || meta.path.is_ident("children_mut")
+                || meta.path.is_ident("children_fold")
+            {
+                let list = if meta.path.is_ident("children") {
+                    &mut children
+                } else if meta.path.is_ident("children_mut") {
+                    &mut children_mut
+                } else {
+                    &mut children_fold
+                };
+                meta.parse_nested_meta(|child| {
+                    let node = child.path.require_ident()?.clone();
+                    list.push((node, child.value()?.parse()?));
+                    Ok(())
+                })
+            } else {
+                Err(meta.error(
+                    "expected `visitor`, `visitor_mut`, `walker_mut`, `fold` or a `children` list",
+                ))
+            }
+        })?;
+    }
+
+    if walker_mut.is_some() && visitor_mut.is_none() {
+        return Err(Error::new_spanned(
+            input,
+            "`walker_mut` needs a `visitor_mut` to implement",
+        ));
+    }
+
+    children.insert(0, (input.ident.clone(), visitor.clone()));
+    Ok(Options {
+        visitor,
+        visitor_mut,
+        walker_mut,
+        children,
+        children_mut,
+        fold,
+        children_fold,
+    })
+}
+
+fn shape(ty: &Type) -> Shape<'_> {
+    let wrapped = |wrapper: &str| -> Option<&Type> {
+        let Type::Path(path) = ty else {
+            return None;
+        };
+        let segment = path.path.segments.last()?;
+        if segment.ident != wrapper {
+            return None;
+        }
+        let PathArguments::AngleBracketed(args) = &segment.arguments else {
+            return None;
+        };
+        match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        }
+    };
+
+    if let Some(inner) = wrapped("Box") {
+        Shape::Boxed(inner)
+    } else if let Some(inner) = wrapped("Vec") {
+        Shape::List(inner)
+    } else if let Some(inner) = wrapped("Option") {
+        Shape::Optional(inner)
+    } else {
+        Shape::Plain(ty)
+    }
+}
+
+/// The type a visitor method takes a field as.
+fn param_type(shape: &Shape) -> TokenStream2 {
+    match shape {
+        Shape::Plain(ty) | Shape::Boxed(ty) => quote!(&#ty),
+        Shape::List(ty) => quote!(&[#ty]),
+        Shape::Optional(ty) => quote!(&Option<#ty>),
+    }
+}
+
+/// The field's own type, as written in the enum.
+fn field_type(shape: &Shape) -> TokenStream2 {
+    match shape {
+        Shape::Plain(ty) => quote!(#ty),
+        Shape::Boxed(ty) => quote!(Box<#ty>),
+        Shape::List(ty) => quote!(Vec<#ty>),
+        Shape::Optional(ty) => quote!(Option<#ty>),
+    }
+}
+
+/// The name of the type a field holds, through any wrapper, if it's a plain
+/// name that could be a node.
+fn field_node<'a>(shape: &Shape<'a>) -> Option<&'a Ident> {
+    let (Shape::Plain(ty) | Shape::Boxed(ty) | Shape::List(ty) | Shape::Optional(ty)) = shape;
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    path.path.get_ident()
+}
+
+/// The visitor trait for the node a field holds, if it holds one.
+fn child_visitor<'a>(children: &'a [(Ident, Path)], shape: &Shape) -> Option<&'a Path> {
+    let ident = field_node(shape)?;
+    children
+        .iter()
+        .find(|(node, _)| node == ident)
+        .map(|(_, visitor)| visitor)
+}
+
+/// Statements visiting the node or nodes in a field with `accept`, which is
+/// `accept::<()>` or `accept_mut`.
+fn visit_child(field: &Field, accept: TokenStream2) -> TokenStream2 {
+    let name = field.name;
+    match field.shape {
+        Shape::Plain(_) | Shape::Boxed(_) => quote! {
+            #name.#accept(visitor);
+        },
+        Shape::List(_) => quote! {
+            for child in #name {
+                child.#accept(visitor);
+            }
+        },
+        Shape::Optional(_) => quote! {
+            if let Some(child) = #name {
+                child.#accept(visitor);
+            }
+        },
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
@@ -0,0 +1,294 @@
+use std::fmt::Display;
+use std::ops::Index;
+
+use crate::token::{Literal, Token};
+
+use super::{expr::Expr, stmt::Stmt};
+
+/// Identifies a node in an `Ast`. Analyses can key side tables on it, as in
+/// `HashMap<NodeId, _>`, rather than on pointers or tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// An expression or statement whose children are `NodeId`s into the `Ast`
+/// holding it. Variants and fields mirror `Expr` and `Stmt`.
+#[derive(Debug, PartialEq)]
+pub enum Node {
+    Assign {
+        name: Token,
+        value: NodeId,
+    },
+    Binary {
+        left: NodeId,
+        right: NodeId,
+        operator: Token,
+    },
+    Call {
+        callee: NodeId,
+        paren: Token,
+        arguments: Vec<NodeId>,
+    },
+    Grouping {
+        left_paren: Token,
+        expression: NodeId,
+        right_paren: Token,
+    },
+    Literal {
+        value: Literal,
+        token: Token,
+    },
+    Unary {
+        operator: Token,
+        right: NodeId,
+    },
+    Variable {
+        name: Token,
+    },
+    Expression {
+        expression: NodeId,
+    },
+    Print {
+        keyword: Token,
+        expression: NodeId,
+    },
+    Var {
+        keyword: Token,
+        name: Token,
+        initializer: Option<NodeId>,
+    },
+}
+
+impl Node {
+    /// Each child, with the field it hangs from, in source order.
+    pub fn children(&self) -> Vec<(&'static str, NodeId)> {
+        match self {
+            Node::Assign { value, .. } => vec![("value", *value)],
+            Node::Binary { left, right, .. } => vec![("left", *left), ("right", *right)],
+            Node::Call {
+                callee, arguments, ..
+            } => {
+                let mut children = vec![("callee", *callee)];
+                children.extend(arguments.iter().map(|argument| ("arguments", *argument)));
+                children
+            }
+            Node::Grouping { expression, .. }
+            | Node::Expression { expression }
+            | Node::Print { expression, .. } => vec![("expression", *expression)],
+            Node::Unary { right, .. } => vec![("right", *right)],
+            Node::Var { initializer, .. } => initializer
+                .iter()
+                .map(|init| ("initializer", *init))
+                .collect(),
+            Node::Literal { .. } | Node::Variable { .. } => vec![],
+        }
+    }
+
+    /// The variant, and the operator, name or value that tells it apart from
+    /// its siblings, as in `Binary +` or `Var x`.
+    pub fn label(&self) -> String {
+        match self {
+            Node::Assign { name, .. } => format!("Assign {}", name.lexeme),
+            Node::Binary { operator, .. } => format!("Binary {}", operator.lexeme),
+            Node::Call { .. } => "Call".to_string(),
+            Node::Grouping { .. } => "Grouping".to_string(),
+            Node::Literal { value, .. } => format!("Literal {}", value.label()),
+            Node::Unary { operator, .. } => format!("Unary {}", operator.lexeme),
+            Node::Variable { name } => format!("Variable {}", name.lexeme),
+            Node::Expression { .. } => "Expression".to_string(),
+            Node::Print { .. } => "Print".to_string(),
+            Node::Var { name, .. } => format!("Var {}", name.lexeme),
+        }
+    }
+}
+
+struct Entry {
+    node: Node,
+    /// The parent, and the field of it this node hangs from.
+    parent: Option<(NodeId, &'static str)>,
+}
+
+/// A program stored flat, every node in one `Vec` and addressed by `NodeId`,
+/// so any node can find its parent and all of them can be walked without
+/// recursion. It's built from the boxed `Expr` and `Stmt` tree the parser
+/// produces and the interpreter runs; the linter and the DOT printer work on
+/// it. Children always come before their parents.
+pub struct Ast {
+    entries: Vec<Entry>,
+    roots: Vec<NodeId>,
+}
+
+impl Ast {
+    pub fn lower(statements: &[Stmt]) -> Self {
+        let mut ast = Ast {
+            entries: Vec::new(),
+            roots: Vec::new(),
+        };
+        for statement in statements {
+            let root = ast.lower_stmt(statement);
+            ast.roots.push(root);
+        }
+        ast
+    }
+
+    /// The top-level statements, in order.
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// The node `id` hangs from and the field it's in, or `None` for a root.
+    pub fn parent(&self, id: NodeId) -> Option<(NodeId, &'static str)> {
+        self.entries[id.0 as usize].parent
+    }
+
+    /// Every node, children before their parents.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (NodeId(i as u32), &entry.node))
+    }
+
+    fn push(&mut self, node: Node) -> NodeId {
+        let id = NodeId(self.entries.len() as u32);
+        for (field, child) in node.children() {
+            self.entries[child.0 as usize].parent = Some((id, field));
+        }
+        self.entries.push(Entry { node, parent: None });
+        id
+    }
+
+    fn lower_stmt(&mut self, statement: &Stmt) -> NodeId {
+        let node = match statement {
+            Stmt::Expression { expression } => Node::Expression {
+                expression: self.lower_expr(expression),
+            },
+            Stmt::Print {
+                keyword,
+                expression,
+            } => Node::Print {
+                keyword: keyword.clone(),
+                expression: self.lower_expr(expression),
+            },
+            Stmt::Var {
+                keyword,
+                name,
+                initializer,
+            } => Node::Var {
+                keyword: keyword.clone(),
+                name: name.clone(),
+                initializer: initializer.as_ref().map(|init| self.lower_expr(init)),
+            },
+        };
+        self.push(node)
+    }
+
+    fn lower_expr(&mut self, expr: &Expr) -> NodeId {
+        let node = match expr {
+            Expr::Assign { name, value } => Node::Assign {
+                name: name.clone(),
+                value: self.lower_expr(value),
+            },
+            Expr::Binary {
+                left,
+                right,
+                operator,
+            } => Node::Binary {
+                left: self.lower_expr(left),
+                right: self.lower_expr(right),
+                operator: operator.clone(),
+            },
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => Node::Call {
+                callee: self.lower_expr(callee),
+                paren: paren.clone(),
+                arguments: arguments
+                    .iter()
+                    .map(|argument| self.lower_expr(argument))
+                    .collect(),
+            },
+            Expr::Grouping {
+                left_paren,
+                expression,
+                right_paren,
+            } => Node::Grouping {
+                left_paren: left_paren.clone(),
+                expression: self.lower_expr(expression),
+                right_paren: right_paren.clone(),
+            },
+            Expr::Literal { value, token } => Node::Literal {
+                value: value.clone(),
+                token: token.clone(),
+            },
+            Expr::Unary { operator, right } => Node::Unary {
+                operator: operator.clone(),
+                right: self.lower_expr(right),
+            },
+            Expr::Variable { name } => Node::Variable { name: name.clone() },
+        };
+        self.push(node)
+    }
+}
+
+impl Index<NodeId> for Ast {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        &self.entries[id.0 as usize].node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuLox;
+
+    fn lower(source: &str) -> Ast {
+        let (statements, _) = RuLox::analyze(source, false);
+        Ast::lower(&statements.expect("source should parse"))
+    }
+
+    #[test]
+    fn every_node_gets_an_id_and_finds_its_parent() {
+        let ast = lower("var a = 1 + 2;\nprint f(a, -a);\n");
+        let labels: Vec<String> = ast.iter().map(|(_, node)| node.label()).collect();
+        assert_eq!(
+            labels,
+            [
+                "Literal 1",
+                "Literal 2",
+                "Binary +",
+                "Var a",
+                "Variable f",
+                "Variable a",
+                "Variable a",
+                "Unary -",
+                "Call",
+                "Print",
+            ]
+        );
+        assert_eq!(ast.roots(), [NodeId(3), NodeId(9)]);
+
+        // Every child points back at the node that lists it
+        for (id, node) in ast.iter() {
+            for (field, child) in node.children() {
+                assert_eq!(ast.parent(child), Some((id, field)));
+            }
+        }
+        for &root in ast.roots() {
+            assert_eq!(ast.parent(root), None);
+        }
+        assert_eq!(
+            ast[NodeId(2)].children(),
+            [("left", NodeId(0)), ("right", NodeId(1))]
+        );
+    }
+}
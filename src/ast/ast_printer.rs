@@ -1,50 +1,88 @@
-use crate::token::{Token, TokenLiteral};
+use crate::token::{Literal, Token};
 
-use super::{expr::Expr, visitor::Visitor};
+use super::{
+    expr::Expr,
+    stmt::Stmt,
+    visitor::{StmtVisitor, Visitor},
+};
 
 pub struct AstPrinter {}
 
 impl AstPrinter {
-    pub fn print(&self, mut expr: Expr) -> String {
+    pub fn print(&self, expr: &Expr) -> String {
         expr.accept::<String>(self)
     }
 
+    pub fn print_stmt(&self, stmt: &Stmt) -> String {
+        stmt.accept::<String>(self)
+    }
+
     fn parenthesize(&self, name: &str, exprs: &mut [&Expr]) -> String {
         let mut sb = String::new();
-        sb.push_str("(");
+        sb.push('(');
         sb.push_str(name);
         for exp in exprs {
-            sb.push_str(" ");
+            sb.push(' ');
             sb.push_str(exp.accept::<String>(self).as_str());
         }
-        sb.push_str(")");
+        sb.push(')');
 
         sb
     }
 }
 
 impl Visitor<String> for AstPrinter {
-    fn visit_binary_expr(&self, left: &Box<Expr>, right: &Box<Expr>, operator: &Token) -> String {
+    fn visit_assign_expr(&self, name: &Token, value: &Expr) -> String {
+        self.parenthesize(&format!("= {}", name.lexeme), &mut [value])
+    }
+
+    fn visit_binary_expr(&self, left: &Expr, right: &Expr, operator: &Token) -> String {
         self.parenthesize(&operator.lexeme, &mut [left, right])
     }
 
-    fn visit_grouping(&self, expression: &Box<Expr>) -> String {
-        self.parenthesize("group", &mut [expression])
+    fn visit_call_expr(&self, callee: &Expr, _paren: &Token, arguments: &[Expr]) -> String {
+        let mut exprs: Vec<&Expr> = vec![callee];
+        exprs.extend(arguments);
+        self.parenthesize("call", &mut exprs)
     }
 
-    fn visit_literal_expr(&self, value: &Option<TokenLiteral>) -> String {
-        let Some(literal) = value else {
-            return "nil".to_string();
-        };
+    fn visit_grouping_expr(
+        &self,
+        _left_paren: &Token,
+        expression: &Expr,
+        _right_paren: &Token,
+    ) -> String {
+        self.parenthesize("group", &mut [expression])
+    }
 
-        if literal.num_val().is_some() {
-            return format!("{}", literal.num_val().unwrap());
-        } else {
-            return format!("{}", literal.string_val().unwrap());
-        }
+    fn visit_literal_expr(&self, value: &Literal, _token: &Token) -> String {
+        value.to_string()
     }
 
-    fn visit_unary_expr(&self, operator: &Token, right: &Box<Expr>) -> String {
+    fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> String {
         self.parenthesize(&operator.lexeme, &mut [right])
     }
+
+    fn visit_variable_expr(&self, name: &Token) -> String {
+        name.lexeme.clone()
+    }
+}
+
+impl StmtVisitor<String> for AstPrinter {
+    fn visit_expression_stmt(&self, expression: &Expr) -> String {
+        self.print(expression)
+    }
+
+    fn visit_print_stmt(&self, _keyword: &Token, expression: &Expr) -> String {
+        self.parenthesize("print", &mut [expression])
+    }
+
+    fn visit_var_stmt(&self, _keyword: &Token, name: &Token, initializer: &Option<Expr>) -> String {
+        match initializer {
+            Some(initializer) => {
+                self.parenthesize(&format!("var {}", name.lexeme), &mut [initializer])
+            }
+            None => format!("(var {})", name.lexeme),
+        }
+    }
 }
@@ -0,0 +1,41 @@
+use super::arena::Ast;
+
+/// Prints a syntax tree as a Graphviz DOT graph, for rendering with
+/// `dot -Tsvg`. Nodes are named after their `NodeId`, every statement hangs
+/// off a `program` root, and edges are labelled with the field they come from.
+pub struct DotPrinter {}
+
+impl DotPrinter {
+    pub fn print(&self, ast: &Ast) -> String {
+        let mut out = String::new();
+        out.push_str("digraph ast {\n");
+        out.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+        out.push_str("  program [label=\"Program\"];\n");
+        for (i, root) in ast.roots().iter().enumerate() {
+            out.push_str(&format!("  program -> n{} [label=\"{}\"];\n", root, i));
+        }
+        for (id, node) in ast.iter() {
+            out.push_str(&format!(
+                "  n{} [label=\"{}\"];\n",
+                id,
+                escape(&node.label())
+            ));
+            if let Some((parent, field)) = ast.parent(id) {
+                out.push_str(&format!(
+                    "  n{} -> n{} [label=\"{}\"];\n",
+                    parent, id, field
+                ));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Escape a label for a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
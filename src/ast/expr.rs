@@ -1,37 +1,76 @@
-use crate::token::{Token, TokenLiteral};
+use rulox_derive::AstNode;
 
-use super::visitor::Visitor;
+use crate::token::{Literal, Token};
 
+#[derive(Debug, PartialEq, AstNode)]
+#[ast(
+    visitor = Visitor,
+    visitor_mut = VisitorMut,
+    walker_mut = WalkerMut,
+    fold = Fold
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
 pub enum Expr {
+    Assign {
+        name: Token,
+        value: Box<Expr>,
+    },
     Binary {
         left: Box<Expr>,
         right: Box<Expr>,
         operator: Token,
     },
+    Call {
+        callee: Box<Expr>,
+        /// The closing ')', which runtime errors in the call point at.
+        paren: Token,
+        arguments: Vec<Expr>,
+    },
     Grouping {
+        left_paren: Token,
         expression: Box<Expr>,
+        right_paren: Token,
     },
     Literal {
-        value: Option<TokenLiteral>,
+        value: Literal,
+        /// The number, string or keyword the value was scanned from, its
+        /// `literal` moved out into `value`.
+        token: Token,
     },
     Unary {
         operator: Token,
         right: Box<Expr>,
     },
+    Variable {
+        name: Token,
+    },
 }
 
 impl Expr {
-    pub fn accept<R>(&self, visitor: &impl Visitor<R>) -> R {
-        use self::Expr::*;
+    /// The token the expression starts with.
+    pub fn first_token(&self) -> &Token {
+        match self {
+            Expr::Assign { name, .. } => name,
+            Expr::Binary { left, .. } => left.first_token(),
+            Expr::Call { callee, .. } => callee.first_token(),
+            Expr::Grouping { left_paren, .. } => left_paren,
+            Expr::Literal { token, .. } => token,
+            Expr::Unary { operator, .. } => operator,
+            Expr::Variable { name } => name,
+        }
+    }
+
+    /// The token the expression ends with.
+    pub fn last_token(&self) -> &Token {
         match self {
-            Binary {
-                left,
-                right,
-                operator,
-            } => visitor.visit_binary_expr(left, right, operator),
-            Grouping { expression } => visitor.visit_grouping(expression),
-            Literal { value } => visitor.visit_literal_expr(value),
-            Unary { operator, right } => visitor.visit_unary_expr(operator, right),
+            Expr::Assign { value, .. } => value.last_token(),
+            Expr::Binary { right, .. } => right.last_token(),
+            Expr::Call { paren, .. } => paren,
+            Expr::Grouping { right_paren, .. } => right_paren,
+            Expr::Literal { token, .. } => token,
+            Expr::Unary { right, .. } => right.last_token(),
+            Expr::Variable { name } => name,
         }
     }
-}
\ No newline at end of file
+}
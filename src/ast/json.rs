@@ -0,0 +1,294 @@
+//! Syntax trees as JSON, for tools that produce or consume them, such as the
+//! web playground and test generators. `rulox ast --format json` writes this
+//! and `rulox run --from-ast` reads it back.
+//!
+//! A document is an object holding the statements of a program, and
+//! optionally the source they were parsed from, so runtime errors can point
+//! at a line and column:
+//!
+//! ```json
+//! {
+//!   "source": "print -x;\n",
+//!   "statements": [
+//!     {
+//!       "kind": "Print",
+//!       "keyword": { "type": "Print", "lexeme": "print", "literal": null, "offset": 0 },
+//!       "expression": {
+//!         "kind": "Unary",
+//!         "operator": { "type": "Minus", "lexeme": "-", "literal": null, "offset": 6 },
+//!         "right": {
+//!           "kind": "Variable",
+//!           "name": { "type": "Identifier", "lexeme": "x", "literal": null, "offset": 7 }
+//!         }
+//!       }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Every node is an object whose `kind` names its variant, with one member
+//! per field, named as in `Expr` and `Stmt`:
+//!
+//! | `kind`       | members                                                          |
+//! |--------------|------------------------------------------------------------------|
+//! | `Assign`     | `name`: token, `value`: expr                                     |
+//! | `Binary`     | `left`: expr, `right`: expr, `operator`: token                   |
+//! | `Call`       | `callee`: expr, `paren`: token, `arguments`: [expr]              |
+//! | `Grouping`   | `left_paren`: token, `expression`: expr, `right_paren`: token    |
+//! | `Literal`    | `value`: literal, `token`: token                                 |
+//! | `Unary`      | `operator`: token, `right`: expr                                 |
+//! | `Variable`   | `name`: token                                                    |
+//! | `Expression` | `expression`: expr                                               |
+//! | `Print`      | `keyword`: token, `expression`: expr                             |
+//! | `Var`        | `keyword`: token, `name`: token, `initializer`: expr or `null`   |
+//!
+//! A token is an object with the same members as in `rulox tokens --json`:
+//! `type` (a `TokenType` variant name), `lexeme`, `literal` and `offset`, the
+//! byte offset of the lexeme in `source`. `literal` and `offset` may be left
+//! out, defaulting to `null` and 0. A literal is the JSON number, boolean or
+//! string it holds, or `null` for `nil`.
+//!
+//! Documents are checked before they run: an operator's `type` must be one
+//! its node can apply, such as `Plus` in a `Binary` but not in a `Unary`, and
+//! every offset must fall on a character boundary in `source`, so a document
+//! without a source leaves them out.
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::token::{Literal, Token, TokenType};
+
+use super::{
+    expr::{self, Expr, WalkerMut},
+    stmt::{self, Stmt, StmtWalkerMut},
+};
+
+const BINARY_OPERATORS: [TokenType; 10] = [
+    TokenType::BangEqual,
+    TokenType::EqualEqual,
+    TokenType::Greater,
+    TokenType::GreaterEqual,
+    TokenType::Less,
+    TokenType::LessEqual,
+    TokenType::Minus,
+    TokenType::Plus,
+    TokenType::Slash,
+    TokenType::Star,
+];
+const UNARY_OPERATORS: [TokenType; 2] = [TokenType::Bang, TokenType::Minus];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Program {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub statements: Vec<Stmt>,
+}
+
+impl Program {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("syntax trees always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, InvalidTree> {
+        let program: Program = serde_json::from_str(json).map_err(InvalidTree::Json)?;
+        let mut validator = Validator {
+            source: program.source.as_deref().unwrap_or_default(),
+            error: None,
+        };
+        for statement in &program.statements {
+            statement.accept_mut(&mut validator);
+        }
+        match validator.error {
+            Some(error) => Err(error),
+            None => Ok(program),
+        }
+    }
+}
+
+/// Why a JSON document isn't a program `rulox` can run.
+#[derive(Debug)]
+pub enum InvalidTree {
+    Json(serde_json::Error),
+    /// An operator its node can't apply, such as a `Binary` with a `Comma`.
+    Operator {
+        kind: &'static str,
+        token: Token,
+    },
+    /// An offset past the end of the source, or inside a character.
+    Offset {
+        token: Token,
+    },
+}
+
+impl Display for InvalidTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidTree::Json(err) => write!(f, "{}", err),
+            InvalidTree::Operator { kind, token } => {
+                write!(f, "`{:?}` is not a {} operator", token.token_type, kind)
+            }
+            InvalidTree::Offset { token } => write!(
+                f,
+                "offset {} of `{}` is not a character boundary in the source",
+                token.offset, token.lexeme
+            ),
+        }
+    }
+}
+
+/// Finds the first token a program can't run with.
+struct Validator<'a> {
+    source: &'a str,
+    error: Option<InvalidTree>,
+}
+
+impl Validator<'_> {
+    fn token(&mut self, token: &Token) {
+        if self.error.is_none() && !self.source.is_char_boundary(token.offset) {
+            self.error = Some(InvalidTree::Offset {
+                token: token.clone(),
+            });
+        }
+    }
+
+    fn operator(&mut self, kind: &'static str, allowed: &[TokenType], token: &Token) {
+        if self.error.is_none() && !allowed.contains(&token.token_type) {
+            self.error = Some(InvalidTree::Operator {
+                kind,
+                token: token.clone(),
+            });
+        }
+        self.token(token);
+    }
+}
+
+impl WalkerMut for Validator<'_> {
+    fn visit_assign_expr(&mut self, name: &Token, value: &Expr) {
+        self.token(name);
+        expr::walk_assign_expr_mut(self, name, value);
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, right: &Expr, operator: &Token) {
+        self.operator("binary", &BINARY_OPERATORS, operator);
+        expr::walk_binary_expr_mut(self, left, right, operator);
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) {
+        self.token(paren);
+        expr::walk_call_expr_mut(self, callee, paren, arguments);
+    }
+
+    fn visit_grouping_expr(&mut self, left_paren: &Token, expression: &Expr, right_paren: &Token) {
+        self.token(left_paren);
+        self.token(right_paren);
+        expr::walk_grouping_expr_mut(self, left_paren, expression, right_paren);
+    }
+
+    fn visit_literal_expr(&mut self, _value: &Literal, token: &Token) {
+        self.token(token);
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) {
+        self.operator("unary", &UNARY_OPERATORS, operator);
+        expr::walk_unary_expr_mut(self, operator, right);
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) {
+        self.token(name);
+    }
+}
+
+impl StmtWalkerMut for Validator<'_> {
+    fn visit_print_stmt(&mut self, keyword: &Token, expression: &Expr) {
+        self.token(keyword);
+        stmt::walk_print_stmt_mut(self, keyword, expression);
+    }
+
+    fn visit_var_stmt(&mut self, keyword: &Token, name: &Token, initializer: &Option<Expr>) {
+        self.token(keyword);
+        self.token(name);
+        stmt::walk_var_stmt_mut(self, keyword, name, initializer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::unparser::Unparser, RuLox};
+
+    #[test]
+    fn programs_survive_a_round_trip() {
+        let source = "var a = (1 + 2.5) * -b;\nprint f(a, \"hi\", true, nil);\na = !false;\n";
+        let (statements, _) = RuLox::analyze(source, false);
+        let program = Program {
+            source: Some(source.to_string()),
+            statements: statements.expect("source should parse"),
+        };
+
+        assert_eq!(Program::from_json(&program.to_json()).unwrap(), program);
+    }
+
+    #[test]
+    fn source_offsets_and_literals_are_optional() {
+        let json = r#"{"statements": [{"kind": "Print", "keyword": {"type": "Print", "lexeme": "print"}, "expression": {
+            "kind": "Binary",
+            "left": {"kind": "Literal", "value": 1, "token": {"type": "Number", "lexeme": "1"}},
+            "right": {"kind": "Literal", "value": "two", "token": {"type": "String", "lexeme": "\"two\""}},
+            "operator": {"type": "Plus", "lexeme": "+"}
+        }}]}"#;
+        let program = Program::from_json(json).unwrap();
+
+        assert_eq!(program.source, None);
+        assert_eq!(
+            Unparser {}.unparse(&program.statements),
+            "print 1 + \"two\";\n"
+        );
+    }
+
+    fn error(json: &str) -> String {
+        Program::from_json(json).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn operators_must_suit_their_node() {
+        let binary = r#"{"statements": [{"kind": "Expression", "expression": {
+            "kind": "Binary",
+            "left": {"kind": "Variable", "name": {"type": "Identifier", "lexeme": "a"}},
+            "right": {"kind": "Variable", "name": {"type": "Identifier", "lexeme": "b"}},
+            "operator": {"type": "Comma", "lexeme": ","}
+        }}]}"#;
+        let unary = r#"{"statements": [{"kind": "Expression", "expression": {
+            "kind": "Unary",
+            "operator": {"type": "Plus", "lexeme": "+"},
+            "right": {"kind": "Variable", "name": {"type": "Identifier", "lexeme": "b"}}
+        }}]}"#;
+
+        assert_eq!(error(binary), "`Comma` is not a binary operator");
+        assert_eq!(error(unary), "`Plus` is not a unary operator");
+    }
+
+    #[test]
+    fn offsets_must_fall_on_a_character_in_the_source() {
+        let program = |offset: usize| {
+            format!(
+                r#"{{"source": "é zz", "statements": [{{"kind": "Print", "keyword": {{"type": "Print", "lexeme": "print"}}, "expression": {{
+                    "kind": "Variable",
+                    "name": {{"type": "Identifier", "lexeme": "zz", "offset": {}}}
+                }}}}]}}"#,
+                offset
+            )
+        };
+
+        assert!(Program::from_json(&program(3)).is_ok());
+        assert!(Program::from_json(&program(5)).is_ok());
+        assert_eq!(
+            error(&program(1)),
+            "offset 1 of `zz` is not a character boundary in the source"
+        );
+        assert_eq!(
+            error(&program(6)),
+            "offset 6 of `zz` is not a character boundary in the source"
+        );
+    }
+}
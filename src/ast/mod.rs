@@ -1,3 +1,11 @@
 pub mod expr;
+pub mod stmt;
 pub mod visitor;
-pub mod ast_printer;
\ No newline at end of file
+pub mod ast_printer;
+pub mod unparser;
+pub mod tree_printer;
+pub mod rpn_printer;
+pub mod dot_printer;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod arena;
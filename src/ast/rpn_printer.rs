@@ -0,0 +1,88 @@
+use crate::token::{Literal, Token, TokenType};
+
+use super::{
+    expr::Expr,
+    stmt::Stmt,
+    visitor::{StmtVisitor, Visitor},
+};
+
+/// Prints expressions in reverse Polish notation, operands before the
+/// operator that applies to them, so `(1 + 2) * (4 - 3)` becomes
+/// `1 2 + 4 3 - *`. Groupings disappear, since the order says it all.
+/// Unary minus prints as `neg` to tell it apart from subtraction, and a call
+/// prints its arity, as in `f 1 2 call/2`.
+pub struct RpnPrinter {}
+
+impl RpnPrinter {
+    pub fn print(&self, expr: &Expr) -> String {
+        expr.accept::<String>(self)
+    }
+
+    pub fn print_stmt(&self, stmt: &Stmt) -> String {
+        stmt.accept::<String>(self)
+    }
+}
+
+impl Visitor<String> for RpnPrinter {
+    fn visit_assign_expr(&self, name: &Token, value: &Expr) -> String {
+        format!("{} {} =", self.print(value), name.lexeme)
+    }
+
+    fn visit_binary_expr(&self, left: &Expr, right: &Expr, operator: &Token) -> String {
+        format!(
+            "{} {} {}",
+            self.print(left),
+            self.print(right),
+            operator.lexeme
+        )
+    }
+
+    fn visit_call_expr(&self, callee: &Expr, _paren: &Token, arguments: &[Expr]) -> String {
+        let mut parts = vec![self.print(callee)];
+        parts.extend(arguments.iter().map(|argument| self.print(argument)));
+        parts.push(format!("call/{}", arguments.len()));
+        parts.join(" ")
+    }
+
+    fn visit_grouping_expr(
+        &self,
+        _left_paren: &Token,
+        expression: &Expr,
+        _right_paren: &Token,
+    ) -> String {
+        self.print(expression)
+    }
+
+    fn visit_literal_expr(&self, value: &Literal, _token: &Token) -> String {
+        value.label()
+    }
+
+    fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> String {
+        let operator = match operator.token_type {
+            TokenType::Minus => "neg",
+            _ => operator.lexeme.as_str(),
+        };
+        format!("{} {}", self.print(right), operator)
+    }
+
+    fn visit_variable_expr(&self, name: &Token) -> String {
+        name.lexeme.clone()
+    }
+}
+
+impl StmtVisitor<String> for RpnPrinter {
+    fn visit_expression_stmt(&self, expression: &Expr) -> String {
+        self.print(expression)
+    }
+
+    fn visit_print_stmt(&self, _keyword: &Token, expression: &Expr) -> String {
+        format!("{} print", self.print(expression))
+    }
+
+    fn visit_var_stmt(&self, _keyword: &Token, name: &Token, initializer: &Option<Expr>) -> String {
+        match initializer {
+            Some(initializer) => format!("{} var {}", self.print(initializer), name.lexeme),
+            None => format!("var {}", name.lexeme),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use rulox_derive::AstNode;
+
+use crate::token::Token;
+
+use super::expr::{Expr, Fold, Visitor, VisitorMut};
+
+#[derive(Debug, PartialEq, AstNode)]
+#[ast(
+    visitor = StmtVisitor,
+    visitor_mut = StmtVisitorMut,
+    walker_mut = StmtWalkerMut,
+    fold = StmtFold,
+    children(Expr = Visitor),
+    children_mut(Expr = VisitorMut),
+    children_fold(Expr = Fold)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
+pub enum Stmt {
+    Expression {
+        expression: Expr,
+    },
+    Print {
+        keyword: Token,
+        expression: Expr,
+    },
+    Var {
+        keyword: Token,
+        name: Token,
+        initializer: Option<Expr>,
+    },
+}
@@ -0,0 +1,169 @@
+use crate::{
+    source_map::{FileId, SourceMap},
+    token::{Literal, Token},
+};
+
+use super::{
+    expr::{self, Expr},
+    stmt::{self, Stmt},
+    visitor::{StmtVisitorMut, VisitorMut},
+};
+
+/// Prints a syntax tree one node per line, indented by depth, with each
+/// node's kind and its span, from its first token to its last. Statements
+/// don't keep their ';', so their spans stop just before it.
+pub struct TreePrinter<'a> {
+    source_map: &'a SourceMap,
+    file: FileId,
+    depth: usize,
+    out: String,
+}
+
+impl<'a> TreePrinter<'a> {
+    pub fn new(source_map: &'a SourceMap, file: FileId) -> Self {
+        TreePrinter {
+            source_map,
+            file,
+            depth: 0,
+            out: String::new(),
+        }
+    }
+
+    pub fn print(mut self, statements: &[Stmt]) -> String {
+        for statement in statements {
+            statement.accept_mut(&mut self);
+        }
+        self.out
+    }
+
+    fn line(&mut self, kind: &str, detail: &str, first: &Token, last: &Token) {
+        self.out.push_str(&"  ".repeat(self.depth));
+        self.out.push_str(kind);
+        if !detail.is_empty() {
+            self.out.push(' ');
+            self.out.push_str(detail);
+        }
+        let start = self.source_map.location(self.file, first.offset);
+        let end = self
+            .source_map
+            .location(self.file, last.offset + last.lexeme.len());
+        self.out.push_str(&format!(
+            " [{}:{}..{}:{}]",
+            start.line, start.column, end.line, end.column
+        ));
+        self.out.push('\n');
+    }
+}
+
+impl VisitorMut<()> for TreePrinter<'_> {
+    fn visit_assign_expr(&mut self, name: &Token, value: &Expr) {
+        self.line("Assign", &name.lexeme, name, value.last_token());
+        self.depth += 1;
+        expr::walk_assign_expr_mut(self, name, value);
+        self.depth -= 1;
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, right: &Expr, operator: &Token) {
+        self.line(
+            "Binary",
+            &operator.lexeme,
+            left.first_token(),
+            right.last_token(),
+        );
+        self.depth += 1;
+        expr::walk_binary_expr_mut(self, left, right, operator);
+        self.depth -= 1;
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) {
+        self.line("Call", "", callee.first_token(), paren);
+        self.depth += 1;
+        expr::walk_call_expr_mut(self, callee, paren, arguments);
+        self.depth -= 1;
+    }
+
+    fn visit_grouping_expr(&mut self, left_paren: &Token, expression: &Expr, right_paren: &Token) {
+        self.line("Grouping", "", left_paren, right_paren);
+        self.depth += 1;
+        expr::walk_grouping_expr_mut(self, left_paren, expression, right_paren);
+        self.depth -= 1;
+    }
+
+    fn visit_literal_expr(&mut self, value: &Literal, token: &Token) {
+        self.line("Literal", &value.label(), token, token);
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) {
+        self.line("Unary", &operator.lexeme, operator, right.last_token());
+        self.depth += 1;
+        expr::walk_unary_expr_mut(self, operator, right);
+        self.depth -= 1;
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) {
+        self.line("Variable", &name.lexeme, name, name);
+    }
+}
+
+impl StmtVisitorMut<()> for TreePrinter<'_> {
+    fn visit_expression_stmt(&mut self, expression: &Expr) {
+        self.line(
+            "Expression",
+            "",
+            expression.first_token(),
+            expression.last_token(),
+        );
+        self.depth += 1;
+        stmt::walk_expression_stmt_mut(self, expression);
+        self.depth -= 1;
+    }
+
+    fn visit_print_stmt(&mut self, keyword: &Token, expression: &Expr) {
+        self.line("Print", "", keyword, expression.last_token());
+        self.depth += 1;
+        stmt::walk_print_stmt_mut(self, keyword, expression);
+        self.depth -= 1;
+    }
+
+    fn visit_var_stmt(&mut self, keyword: &Token, name: &Token, initializer: &Option<Expr>) {
+        let last = initializer.as_ref().map_or(name, Expr::last_token);
+        self.line("Var", &name.lexeme, keyword, last);
+        self.depth += 1;
+        stmt::walk_var_stmt_mut(self, keyword, name, initializer);
+        self.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuLox;
+
+    #[test]
+    fn every_line_spans_its_whole_node() {
+        let source = "var a = (1 + \"é\");\nprint -a;\nf(true);\nvar b;\n";
+        let mut source_map = SourceMap::default();
+        let file = source_map.add("test.lox", source.to_string());
+        let (statements, _) = RuLox::analyze(source, false);
+        let tree = TreePrinter::new(&source_map, file).print(&statements.unwrap());
+
+        assert_eq!(
+            tree,
+            "\
+Var a [1:1..1:18]
+  Grouping [1:9..1:18]
+    Binary + [1:10..1:17]
+      Literal 1 [1:10..1:11]
+      Literal \"é\" [1:14..1:17]
+Print [2:1..2:9]
+  Unary - [2:7..2:9]
+    Variable a [2:8..2:9]
+Expression [3:1..3:8]
+  Call [3:1..3:8]
+    Variable f [3:1..3:2]
+    Literal true [3:3..3:7]
+Var b [4:1..4:6]
+"
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use crate::token::{Literal, Token, TokenType};
+
+use super::{
+    expr::Expr,
+    stmt::Stmt,
+    visitor::{StmtVisitor, Visitor},
+};
+
+/// How tightly an expression binds, loosest first, following the grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Assignment,
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+    Unary,
+    Call,
+    Primary,
+}
+
+impl Precedence {
+    fn of(expr: &Expr) -> Self {
+        match expr {
+            Expr::Assign { .. } => Precedence::Assignment,
+            Expr::Binary { operator, .. } => Self::of_operator(operator),
+            Expr::Call { .. } => Precedence::Call,
+            Expr::Unary { .. } => Precedence::Unary,
+            Expr::Grouping { .. } | Expr::Literal { .. } | Expr::Variable { .. } => {
+                Precedence::Primary
+            }
+        }
+    }
+
+    fn of_operator(operator: &Token) -> Self {
+        use TokenType::*;
+        match operator.token_type {
+            BangEqual | EqualEqual => Precedence::Equality,
+            Greater | GreaterEqual | Less | LessEqual => Precedence::Comparison,
+            Minus | Plus => Precedence::Term,
+            Slash | Star => Precedence::Factor,
+            _ => unreachable!("not a binary operator: {:?}", operator.token_type),
+        }
+    }
+
+    /// The next level up, for the right operand of a left-associative operator.
+    fn tighter(self) -> Self {
+        use Precedence::*;
+        match self {
+            Assignment => Equality,
+            Equality => Comparison,
+            Comparison => Term,
+            Term => Factor,
+            Factor => Unary,
+            Unary => Call,
+            Call | Primary => Primary,
+        }
+    }
+}
+
+/// Turns a syntax tree back into Lox source. Parentheses in the tree (as
+/// `Grouping`s) are kept, and more are added only where precedence needs
+/// them, so the output parses back into the same tree. Comments aren't part
+/// of the tree, so they don't survive.
+pub struct Unparser {}
+
+impl Unparser {
+    /// A whole program, one statement per line.
+    pub fn unparse(&self, statements: &[Stmt]) -> String {
+        statements
+            .iter()
+            .map(|statement| statement.accept::<String>(self) + "\n")
+            .collect()
+    }
+
+    pub fn unparse_expr(&self, expr: &Expr) -> String {
+        expr.accept::<String>(self)
+    }
+
+    /// An operand, parenthesized if it binds more loosely than `precedence`.
+    fn operand(&self, expr: &Expr, precedence: Precedence) -> String {
+        if Precedence::of(expr) < precedence {
+            format!("({})", self.unparse_expr(expr))
+        } else {
+            self.unparse_expr(expr)
+        }
+    }
+}
+
+impl Visitor<String> for Unparser {
+    fn visit_assign_expr(&self, name: &Token, value: &Expr) -> String {
+        // Right-associative, so `a = b = c` needs no parentheses
+        format!(
+            "{} = {}",
+            name.lexeme,
+            self.operand(value, Precedence::Assignment)
+        )
+    }
+
+    fn visit_binary_expr(&self, left: &Expr, right: &Expr, operator: &Token) -> String {
+        let precedence = Precedence::of_operator(operator);
+        format!(
+            "{} {} {}",
+            self.operand(left, precedence),
+            operator.lexeme,
+            self.operand(right, precedence.tighter())
+        )
+    }
+
+    fn visit_call_expr(&self, callee: &Expr, _paren: &Token, arguments: &[Expr]) -> String {
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|argument| self.operand(argument, Precedence::Assignment))
+            .collect();
+        format!(
+            "{}({})",
+            self.operand(callee, Precedence::Call),
+            arguments.join(", ")
+        )
+    }
+
+    fn visit_grouping_expr(
+        &self,
+        _left_paren: &Token,
+        expression: &Expr,
+        _right_paren: &Token,
+    ) -> String {
+        format!("({})", self.unparse_expr(expression))
+    }
+
+    fn visit_literal_expr(&self, value: &Literal, _token: &Token) -> String {
+        match value {
+            Literal::Str(val) => format!("\"{}\"", val),
+            _ => value.to_string(),
+        }
+    }
+
+    fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> String {
+        format!(
+            "{}{}",
+            operator.lexeme,
+            self.operand(right, Precedence::Unary)
+        )
+    }
+
+    fn visit_variable_expr(&self, name: &Token) -> String {
+        name.lexeme.clone()
+    }
+}
+
+impl StmtVisitor<String> for Unparser {
+    fn visit_expression_stmt(&self, expression: &Expr) -> String {
+        format!("{};", self.unparse_expr(expression))
+    }
+
+    fn visit_print_stmt(&self, _keyword: &Token, expression: &Expr) -> String {
+        format!("print {};", self.unparse_expr(expression))
+    }
+
+    fn visit_var_stmt(&self, _keyword: &Token, name: &Token, initializer: &Option<Expr>) -> String {
+        match initializer {
+            Some(initializer) => {
+                format!("var {} = {};", name.lexeme, self.unparse_expr(initializer))
+            }
+            None => format!("var {};", name.lexeme),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::{expr::Fold, stmt::StmtFold},
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    /// xorshift64*, so the test needs no extra dependencies and every run
+    /// sees the same trees.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.below(items.len())]
+        }
+    }
+
+    const NAMES: [&str; 5] = ["a", "b", "count", "x2", "total"];
+    const WORDS: [&str; 4] = ["", "hi", "two words", "line\nbreak"];
+    const BINARY: [(TokenType, &str); 10] = [
+        (TokenType::BangEqual, "!="),
+        (TokenType::EqualEqual, "=="),
+        (TokenType::Greater, ">"),
+        (TokenType::GreaterEqual, ">="),
+        (TokenType::Less, "<"),
+        (TokenType::LessEqual, "<="),
+        (TokenType::Minus, "-"),
+        (TokenType::Plus, "+"),
+        (TokenType::Slash, "/"),
+        (TokenType::Star, "*"),
+    ];
+
+    fn token(token_type: TokenType, lexeme: &str) -> Token {
+        Token::new(token_type, lexeme.to_string(), None, 0)
+    }
+
+    fn literal(value: Literal) -> Expr {
+        let (token_type, lexeme) = match &value {
+            Literal::Number(_) => (TokenType::Number, value.to_string()),
+            Literal::Str(val) => (TokenType::String, format!("\"{}\"", val)),
+            Literal::Bool(true) => (TokenType::True, value.to_string()),
+            Literal::Bool(false) => (TokenType::False, value.to_string()),
+            Literal::Nil => (TokenType::Nil, value.to_string()),
+        };
+        Expr::Literal {
+            token: token(token_type, &lexeme),
+            value,
+        }
+    }
+
+    fn random_expr(rng: &mut Rng, depth: usize) -> Expr {
+        let choice = if depth == 0 {
+            rng.below(2)
+        } else {
+            rng.below(7)
+        };
+        match choice {
+            0 => {
+                let value = match rng.below(5) {
+                    0 => Literal::Nil,
+                    1 => Literal::Bool(rng.below(2) == 0),
+                    2 => Literal::Str(rng.pick(&WORDS).to_string()),
+                    _ => Literal::Number(rng.below(4000) as f64 / 4.0),
+                };
+                literal(value)
+            }
+            1 => Expr::Variable {
+                name: token(TokenType::Identifier, rng.pick(&NAMES)),
+            },
+            2 => Expr::Assign {
+                name: token(TokenType::Identifier, rng.pick(&NAMES)),
+                value: Box::new(random_expr(rng, depth - 1)),
+            },
+            3 => {
+                let (token_type, lexeme) = BINARY[rng.below(BINARY.len())];
+                Expr::Binary {
+                    left: Box::new(random_expr(rng, depth - 1)),
+                    right: Box::new(random_expr(rng, depth - 1)),
+                    operator: token(token_type, lexeme),
+                }
+            }
+            4 => Expr::Call {
+                callee: Box::new(random_expr(rng, depth - 1)),
+                paren: token(TokenType::RightParen, ")"),
+                arguments: (0..rng.below(3))
+                    .map(|_| random_expr(rng, depth - 1))
+                    .collect(),
+            },
+            5 => Expr::Grouping {
+                left_paren: token(TokenType::LeftParen, "("),
+                expression: Box::new(random_expr(rng, depth - 1)),
+                right_paren: token(TokenType::RightParen, ")"),
+            },
+            _ => {
+                let (token_type, lexeme) = if rng.below(2) == 0 {
+                    (TokenType::Minus, "-")
+                } else {
+                    (TokenType::Bang, "!")
+                };
+                Expr::Unary {
+                    operator: token(token_type, lexeme),
+                    right: Box::new(random_expr(rng, depth - 1)),
+                }
+            }
+        }
+    }
+
+    fn random_stmt(rng: &mut Rng) -> Stmt {
+        let depth = rng.below(5);
+        match rng.below(4) {
+            0 => Stmt::Expression {
+                expression: random_expr(rng, depth),
+            },
+            1 => Stmt::Print {
+                keyword: token(TokenType::Print, "print"),
+                expression: random_expr(rng, depth),
+            },
+            2 => Stmt::Var {
+                keyword: token(TokenType::Var, "var"),
+                name: token(TokenType::Identifier, rng.pick(&NAMES)),
+                initializer: None,
+            },
+            _ => Stmt::Var {
+                keyword: token(TokenType::Var, "var"),
+                name: token(TokenType::Identifier, rng.pick(&NAMES)),
+                initializer: Some(random_expr(rng, depth)),
+            },
+        }
+    }
+
+    fn parse(source: &str) -> Vec<Stmt> {
+        let tokens = Scanner::new(source)
+            .map(|token| token.expect("unparsed source should scan"))
+            .take_while(|token| token.token_type != TokenType::Eof)
+            .chain([token(TokenType::Eof, "")])
+            .collect();
+        Parser::new(tokens)
+            .parse()
+            .unwrap_or_else(|errors| panic!("{:?} parsing:\n{}", errors, source))
+    }
+
+    /// The unparser adds parentheses the tree didn't have, which parse as
+    /// extra `Grouping`s, so trees are compared without them.
+    struct StripGroupings;
+
+    impl Fold for StripGroupings {
+        fn fold_grouping_expr(
+            &mut self,
+            _left_paren: Token,
+            expression: Box<Expr>,
+            _right_paren: Token,
+        ) -> Expr {
+            self.fold_expr(*expression)
+        }
+    }
+
+    impl StmtFold for StripGroupings {}
+
+    fn strip(statements: Vec<Stmt>) -> Vec<Stmt> {
+        statements
+            .into_iter()
+            .map(|statement| StripGroupings.fold_stmt(statement))
+            .collect()
+    }
+
+    #[test]
+    fn unparsed_source_parses_back_to_the_same_tree() {
+        let unparser = Unparser {};
+        let mut rng = Rng(0x5eed_1e55_c0ff_ee00);
+        for _ in 0..2000 {
+            let program: Vec<Stmt> = (0..1 + rng.below(3))
+                .map(|_| random_stmt(&mut rng))
+                .collect();
+            let source = unparser.unparse(&program);
+            let reparsed = parse(&source);
+
+            // Formatting is stable, and keeps every parenthesis it wrote
+            assert_eq!(unparser.unparse(&reparsed), source);
+            assert_eq!(strip(reparsed), strip(program), "source:\n{}", source);
+        }
+    }
+
+    #[test]
+    fn adds_only_the_parentheses_precedence_needs() {
+        let unparser = Unparser {};
+        let cases = [
+            "print 1 + 2 * 3;",
+            "print (1 + 2) * 3;",
+            "print 1 - (2 - 3);",
+            "print 1 - 2 - 3;",
+            "a = b = !-c;",
+            "print -(1 + 2) == !true;",
+            "f(a, b = 1)(2);",
+        ];
+        for source in cases {
+            let source = format!("{}\n", source);
+            assert_eq!(unparser.unparse(&parse(&source)), source);
+        }
+
+        let sum = |left, right| Expr::Binary {
+            left: Box::new(left),
+            right: Box::new(right),
+            operator: token(TokenType::Plus, "+"),
+        };
+        let number = |n| literal(Literal::Number(n));
+        let tree = Expr::Binary {
+            left: Box::new(sum(number(1.0), number(2.0))),
+            right: Box::new(sum(number(3.0), number(4.0))),
+            operator: token(TokenType::Star, "*"),
+        };
+        assert_eq!(unparser.unparse_expr(&tree), "(1 + 2) * (3 + 4)");
+    }
+}
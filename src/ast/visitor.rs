@@ -1,10 +1,6 @@
-use crate::token::{Token, TokenLiteral};
+//! The visitor traits live next to the enums they visit, where
+//! `#[derive(AstNode)]` generates them. They're gathered here so visitors
+//! have one place to import them from.
 
-use super::expr::Expr;
-
-pub trait Visitor<R> {
-    fn visit_binary_expr(&self, left: &Box<Expr>, right: &Box<Expr>, operator: &Token) -> R;
-    fn visit_grouping(&self, expression: &Box<Expr>) -> R;
-    fn visit_literal_expr(&self, value: &Option<TokenLiteral>) -> R;
-    fn visit_unary_expr(&self, operator: &Token, right: &Box<Expr>) -> R;
-}
+pub use super::expr::{Visitor, VisitorMut};
+pub use super::stmt::{StmtVisitor, StmtVisitorMut};
@@ -0,0 +1,484 @@
+use std::io::{self, IsTerminal};
+
+use crate::lint::{Lint, LintLevel, LintLevels};
+
+// Exit codes, following sysexits.h
+pub const EX_USAGE: i32 = 64;
+pub const EX_DATAERR: i32 = 65;
+pub const EX_NOINPUT: i32 = 66;
+pub const EX_SOFTWARE: i32 = 70;
+pub const EX_IOERR: i32 = 74;
+
+/// Subcommands, as opposed to scripts to run.
+const COMMANDS: [&str; 8] = [
+    "run", "repl", "tokens", "ast", "check", "fix", "fmt", "desugar",
+];
+
+pub const USAGE: &str = "\
+Usage: rulox [options] [script [args...]]
+       rulox [options] -e <source> [args...]
+       rulox [options] <command> [args]
+
+A script of '-' is read from stdin, as is a script piped in with no arguments.
+Anything after the script is passed on to it, and can be read with args() and
+arg(i).
+
+Commands:
+  run [--watch] <script> [args...]
+                               run a script, with --watch re-running it
+                               whenever it changes
+  run --from-ast <file.json> [args...]
+                               run a syntax tree written by 'ast --format json'
+  repl                         start an interactive session (the default)
+  tokens [--json] <script>     print the tokens the scanner produces
+  ast [--format <format>] <script>
+                               print the syntax tree the parser produces, as
+                               sexpr (the default), tree, rpn, dot or json
+  check <script>               parse and lint a script without running it
+  fix [--dry-run] <script>     apply machine-applicable fixes in place
+  fmt <script>                 print a script reformatted (comments are lost)
+  desugar <script>             print a script as the interpreter sees it, with
+                               syntactic sugar lowered to core Lox
+
+Options:
+  -e <source>                  run <source> as a script
+  -W <lint>                    report a lint as a warning (the default)
+  -A <lint>                    allow a lint, silencing it
+  -D <lint>                    deny a lint, making it an error
+  -h, --help                   print this message
+  -V, --version                print the version";
+
+/// Syntax trees as JSON need the optional `serde` feature.
+const WITHOUT_SERDE: &str =
+    "rulox was built without JSON support. Rebuild it with '--features serde'.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AstFormat {
+    /// Lisp-style, as the book's `AstPrinter` prints it.
+    Sexpr,
+    /// One node per line, indented, with kinds and spans.
+    Tree,
+    /// Reverse Polish notation, from the book's challenges.
+    Rpn,
+    /// Graphviz DOT, for rendering the tree as an image.
+    Dot,
+    /// The JSON schema documented in `ast::json`.
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+impl AstFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sexpr" => Some(AstFormat::Sexpr),
+            "tree" => Some(AstFormat::Tree),
+            "rpn" => Some(AstFormat::Rpn),
+            "dot" => Some(AstFormat::Dot),
+            #[cfg(feature = "serde")]
+            "json" => Some(AstFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Subcommand {
+    Run {
+        script: String,
+    },
+    Watch {
+        script: String,
+    },
+    /// Run a syntax tree read from JSON rather than parsed from source.
+    #[cfg(feature = "serde")]
+    RunAst {
+        script: String,
+    },
+    Eval {
+        source: String,
+    },
+    Repl,
+    Tokens {
+        script: String,
+        format: TokenFormat,
+    },
+    Ast {
+        script: String,
+        format: AstFormat,
+    },
+    Check {
+        script: String,
+    },
+    Fix {
+        script: String,
+        dry_run: bool,
+    },
+    Fmt {
+        script: String,
+    },
+    Desugar {
+        script: String,
+    },
+    Help,
+    Version,
+}
+
+#[derive(Debug)]
+pub struct Cli {
+    pub lint_levels: LintLevels,
+    /// Arguments for the script itself, following it on the command line.
+    pub script_args: Vec<String>,
+    pub subcommand: Subcommand,
+}
+
+impl Cli {
+    /// Parse the command line, minus the program name. The error is a message
+    /// to print alongside `USAGE`.
+    pub fn parse(args: &[String]) -> Result<Cli, String> {
+        let (args, script_args) = split_script_args(args);
+        let script_args = script_args.to_vec();
+        let (lint_levels, args) = parse_lint_flags(args)?;
+        let (eval, args) = parse_value_flag(&args, "-e", "source")?;
+        let (format, args) = parse_value_flag(&args, "--format", "format")?;
+        let (flags, positional): (Vec<&str>, Vec<&str>) = args
+            .iter()
+            .map(String::as_str)
+            .partition(|arg| arg.starts_with('-') && *arg != "-");
+
+        let has_flag = |names: &[&str]| flags.iter().any(|flag| names.contains(flag));
+        let allow_flags = |allowed: &[&str]| match flags.iter().find(|f| !allowed.contains(f)) {
+            Some(flag) => Err(format!("Unexpected option '{}'.", flag)),
+            None => Ok(()),
+        };
+        let one_script = |command: &str| match positional[1..] {
+            [script] => Ok(script.to_string()),
+            _ => Err(format!("'{}' takes exactly one script.", command)),
+        };
+
+        if has_flag(&["-h", "--help"]) {
+            return Ok(Cli {
+                lint_levels,
+                script_args,
+                subcommand: Subcommand::Help,
+            });
+        }
+        if has_flag(&["-V", "--version"]) {
+            return Ok(Cli {
+                lint_levels,
+                script_args,
+                subcommand: Subcommand::Version,
+            });
+        }
+
+        if format.is_some() && positional.first() != Some(&"ast") {
+            return Err("'--format' only applies to 'ast'.".to_string());
+        }
+
+        if let Some(source) = eval {
+            allow_flags(&[])?;
+            if !positional.is_empty() {
+                return Err("'-e' can't be combined with a script or command.".to_string());
+            }
+            return Ok(Cli {
+                lint_levels,
+                script_args,
+                subcommand: Subcommand::Eval { source },
+            });
+        }
+
+        let subcommand = match positional.first().copied() {
+            // Piped input is a script, not something to prompt for
+            None if !io::stdin().is_terminal() => {
+                allow_flags(&[])?;
+                Subcommand::Run {
+                    script: "-".to_string(),
+                }
+            }
+            None => {
+                allow_flags(&[])?;
+                Subcommand::Repl
+            }
+            Some("repl") => {
+                allow_flags(&[])?;
+                match positional[1..] {
+                    [] => Subcommand::Repl,
+                    _ => return Err("'repl' takes no arguments.".to_string()),
+                }
+            }
+            Some("run") => {
+                allow_flags(&["--watch", "--from-ast"])?;
+                let script = one_script("run")?;
+                if has_flag(&["--from-ast"]) {
+                    if has_flag(&["--watch"]) {
+                        return Err("'--watch' can't be combined with '--from-ast'.".to_string());
+                    }
+                    from_ast(script)?
+                } else if !has_flag(&["--watch"]) {
+                    Subcommand::Run { script }
+                } else if script == "-" {
+                    return Err("Can't watch stdin.".to_string());
+                } else {
+                    Subcommand::Watch { script }
+                }
+            }
+            Some("tokens") => {
+                allow_flags(&["--json"])?;
+                let format = if has_flag(&["--json"]) {
+                    TokenFormat::Json
+                } else {
+                    TokenFormat::Text
+                };
+                Subcommand::Tokens {
+                    script: one_script("tokens")?,
+                    format,
+                }
+            }
+            Some("ast") => {
+                allow_flags(&[])?;
+                let format = match format {
+                    None => AstFormat::Sexpr,
+                    Some(name) if name == "json" && !cfg!(feature = "serde") => {
+                        return Err(WITHOUT_SERDE.to_string())
+                    }
+                    Some(name) => AstFormat::from_name(&name).ok_or_else(|| {
+                        format!(
+                            "Unknown format '{}'. Expected sexpr, tree, rpn, dot or json.",
+                            name
+                        )
+                    })?,
+                };
+                Subcommand::Ast {
+                    script: one_script("ast")?,
+                    format,
+                }
+            }
+            Some("check") => {
+                allow_flags(&[])?;
+                Subcommand::Check {
+                    script: one_script("check")?,
+                }
+            }
+            Some("fix") => {
+                allow_flags(&["--dry-run"])?;
+                Subcommand::Fix {
+                    script: one_script("fix")?,
+                    dry_run: has_flag(&["--dry-run"]),
+                }
+            }
+            Some("fmt") => {
+                allow_flags(&[])?;
+                Subcommand::Fmt {
+                    script: one_script("fmt")?,
+                }
+            }
+            Some("desugar") => {
+                allow_flags(&[])?;
+                Subcommand::Desugar {
+                    script: one_script("desugar")?,
+                }
+            }
+            // `rulox script.lox` is short for `rulox run script.lox`. Anything
+            // after the script has already been split off as its arguments.
+            Some(script) => {
+                allow_flags(&[])?;
+                Subcommand::Run {
+                    script: script.to_string(),
+                }
+            }
+        };
+
+        Ok(Cli {
+            lint_levels,
+            script_args,
+            subcommand,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+fn from_ast(script: String) -> Result<Subcommand, String> {
+    Ok(Subcommand::RunAst { script })
+}
+
+#[cfg(not(feature = "serde"))]
+fn from_ast(_script: String) -> Result<Subcommand, String> {
+    Err(WITHOUT_SERDE.to_string())
+}
+
+/// Split off the arguments meant for the script being run: everything after
+/// the script, or after the options when the source is given with `-e`.
+/// Flags among them belong to the script, not to us.
+fn split_script_args(args: &[String]) -> (&[String], &[String]) {
+    let mut eval = false;
+    let mut command = None;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if matches!(arg, "-W" | "-A" | "-D" | "-e" | "--format") {
+            // Skip the flag's value too
+            eval |= arg == "-e";
+            i += 2;
+            continue;
+        }
+        if arg.starts_with('-') && arg != "-" {
+            i += 1;
+            continue;
+        }
+
+        if eval {
+            return args.split_at(i);
+        }
+        match command {
+            None if COMMANDS.contains(&arg) => {
+                if arg != "run" {
+                    return (args, &[]);
+                }
+                command = Some(arg);
+            }
+            _ => return args.split_at(i + 1),
+        }
+        i += 1;
+    }
+
+    (args, &[])
+}
+
+/// Pull `-W name`, `-A name` and `-D name` (or the joined `-Wname` forms) out of
+/// the command line, returning the configured levels and whatever is left over.
+fn parse_lint_flags(args: &[String]) -> Result<(LintLevels, Vec<String>), String> {
+    let mut levels = LintLevels::default();
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let level = match arg.get(..2) {
+            Some("-W") => LintLevel::Warn,
+            Some("-A") => LintLevel::Allow,
+            Some("-D") => LintLevel::Deny,
+            _ => {
+                rest.push(arg.clone());
+                continue;
+            }
+        };
+
+        let name = if arg.len() > 2 {
+            arg[2..].to_string()
+        } else {
+            match iter.next() {
+                Some(name) => name.clone(),
+                None => return Err(format!("Missing lint name after '{}'.", arg)),
+            }
+        };
+
+        let Some(lint) = Lint::from_name(&name) else {
+            let known: Vec<&str> = Lint::ALL.iter().map(|lint| lint.name()).collect();
+            return Err(format!(
+                "Unknown lint '{}'. Known lints: {}.",
+                name,
+                known.join(", ")
+            ));
+        };
+        levels.set(lint, level);
+    }
+
+    Ok((levels, rest))
+}
+
+/// Pull a flag that takes a value, like `-e source` or `--format tree`, out of
+/// the command line, returning the value (if any) and whatever is left over.
+fn parse_value_flag(
+    args: &[String],
+    flag: &str,
+    what: &str,
+) -> Result<(Option<String>, Vec<String>), String> {
+    let mut value = None;
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg != flag {
+            rest.push(arg.clone());
+            continue;
+        }
+        match (iter.next(), &value) {
+            (None, _) => return Err(format!("Missing {} after '{}'.", what, flag)),
+            (Some(_), Some(_)) => return Err(format!("'{}' can only be given once.", flag)),
+            (Some(next), None) => value = Some(next.clone()),
+        }
+    }
+
+    Ok((value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(command_line: &str) -> Result<Cli, String> {
+        let args: Vec<String> = command_line.split(' ').map(String::from).collect();
+        Cli::parse(&args)
+    }
+
+    #[test]
+    fn flags_after_the_script_belong_to_it() {
+        let cli = parse("s.lox -W x").unwrap();
+        assert!(matches!(&cli.subcommand, Subcommand::Run { script } if script == "s.lox"));
+        assert_eq!(cli.script_args, ["-W", "x"]);
+        assert_eq!(
+            cli.lint_levels.level(Lint::MixedTypeComparison),
+            LintLevel::Warn
+        );
+    }
+
+    #[test]
+    fn run_watches_and_passes_arguments_on() {
+        let cli = parse("run --watch s.lox a").unwrap();
+        assert!(matches!(&cli.subcommand, Subcommand::Watch { script } if script == "s.lox"));
+        assert_eq!(cli.script_args, ["a"]);
+
+        assert_eq!(parse("run --watch -").unwrap_err(), "Can't watch stdin.");
+    }
+
+    #[test]
+    fn eval_takes_everything_after_its_source_as_arguments() {
+        let cli = parse("-e src a b").unwrap();
+        assert!(matches!(&cli.subcommand, Subcommand::Eval { source } if source == "src"));
+        assert_eq!(cli.script_args, ["a", "b"]);
+
+        assert_eq!(parse("-e").unwrap_err(), "Missing source after '-e'.");
+    }
+
+    #[test]
+    fn options_with_values_can_come_before_the_command() {
+        let cli = parse("-D mixed_type_comparison ast --format tree f").unwrap();
+        assert!(matches!(
+            &cli.subcommand,
+            Subcommand::Ast { script, format: AstFormat::Tree } if script == "f"
+        ));
+        assert!(cli.script_args.is_empty());
+        assert_eq!(
+            cli.lint_levels.level(Lint::MixedTypeComparison),
+            LintLevel::Deny
+        );
+
+        assert_eq!(
+            parse("--format tree check f").unwrap_err(),
+            "'--format' only applies to 'ast'."
+        );
+    }
+
+    #[test]
+    fn commands_other_than_run_take_no_script_arguments() {
+        assert_eq!(
+            parse("check f extra").unwrap_err(),
+            "'check' takes exactly one script."
+        );
+        assert!(parse("-W nonsense f")
+            .unwrap_err()
+            .starts_with("Unknown lint 'nonsense'."));
+    }
+}
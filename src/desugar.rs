@@ -0,0 +1,19 @@
+//! Passes that lower syntactic sugar into the core language the interpreter
+//! runs, so it only ever sees the core forms. `rulox desugar` prints what
+//! they produce.
+
+use crate::ast::stmt::Stmt;
+
+/// A pass rewrites a whole program, usually with a `StmtFold`.
+type Pass = fn(Vec<Stmt>) -> Vec<Stmt>;
+
+/// Every pass, in the order they run. The language has no sugar yet, so
+/// there are none: `for` loops, compound assignment and string interpolation
+/// each add theirs here as they arrive.
+const PASSES: &[Pass] = &[];
+
+pub fn desugar(statements: Vec<Stmt>) -> Vec<Stmt> {
+    PASSES
+        .iter()
+        .fold(statements, |statements, pass| pass(statements))
+}
@@ -0,0 +1,365 @@
+use crate::{
+    interpreter::RuntimeError,
+    lint::{self, Lint},
+    parser::ParseError,
+    scanner::ScanError,
+    token::{Token, TokenType},
+};
+
+/// A run of bytes in a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Replace the text covered by `span` with `replacement`. An empty span inserts.
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub fn insert(offset: usize, text: &str) -> Self {
+        Edit {
+            span: Span { offset, len: 0 },
+            replacement: text.to_string(),
+        }
+    }
+}
+
+/// A suggested change that would resolve a diagnostic.
+#[derive(Debug, Clone)]
+pub struct Fix {
+    pub message: String,
+    pub edits: Vec<Edit>,
+    /// Whether the edits can be applied without a human checking them first,
+    /// which is what `rulox fix` looks for.
+    pub machine_applicable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Byte offset the diagnostic points at.
+    pub offset: usize,
+    /// Where in the line things went wrong, e.g. " at ')'" or " at end".
+    pub context: String,
+    pub message: String,
+    pub lint: Option<Lint>,
+    pub fix: Option<Fix>,
+}
+
+impl Diagnostic {
+    pub fn error(offset: usize, message: &str) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            offset,
+            context: String::new(),
+            message: message.to_string(),
+            lint: None,
+            fix: None,
+        }
+    }
+
+    fn at_token(token: &Token, message: &str) -> Self {
+        let context = if token.token_type == TokenType::Eof {
+            " at end".to_string()
+        } else {
+            format!(" at '{}'", token.lexeme)
+        };
+
+        Diagnostic {
+            context,
+            ..Diagnostic::error(token.offset, message)
+        }
+    }
+
+    fn with_fix(mut self, message: &str, edits: Vec<Edit>, machine_applicable: bool) -> Self {
+        self.fix = Some(Fix {
+            message: message.to_string(),
+            edits,
+            machine_applicable,
+        });
+        self
+    }
+
+    /// The diagnostic for a parse error in `source`. Only a ';' missing at
+    /// the end of a line, before a '}' or at the end of the file is fixed
+    /// without asking: anywhere else the statement may have been meant to
+    /// go on, and a guessed keyword or ')' may change what the program means.
+    pub fn from_parse_error(err: ParseError, source: &str) -> Self {
+        use ParseError::*;
+        match err {
+            ExpectedExpression { token } => Diagnostic::at_token(&token, "Expect expression."),
+            UnclosedGrouping { token, expected_at } => Diagnostic::at_token(
+                &token,
+                "Expect ')' after expression.",
+            )
+            .with_fix("insert ')'", vec![Edit::insert(expected_at, ")")], false),
+            MissingSemicolon {
+                token,
+                expected_at,
+                after,
+            } => {
+                let ends_statement =
+                    matches!(token.token_type, TokenType::RightBrace | TokenType::Eof)
+                        || source
+                            .get(expected_at..token.offset)
+                            .is_some_and(|between| between.contains('\n'));
+                Diagnostic::at_token(&token, &format!("Expect ';' after {}.", after)).with_fix(
+                    "insert ';'",
+                    vec![Edit::insert(expected_at, ";")],
+                    ends_statement,
+                )
+            }
+            MisspelledKeyword { token, keyword } => Diagnostic::at_token(
+                &token,
+                &format!("Unexpected identifier. Did you mean '{}'?", keyword),
+            )
+            .with_fix(
+                &format!("replace '{}' with '{}'", token.lexeme, keyword),
+                vec![Edit {
+                    span: Span {
+                        offset: token.offset,
+                        len: token.lexeme.len(),
+                    },
+                    replacement: keyword.to_string(),
+                }],
+                false,
+            ),
+            UnclosedCall { token, expected_at } => Diagnostic::at_token(
+                &token,
+                "Expect ')' after arguments.",
+            )
+            .with_fix("insert ')'", vec![Edit::insert(expected_at, ")")], false),
+            ExpectedVariableName { token } => Diagnostic::at_token(&token, "Expect variable name."),
+            InvalidAssignmentTarget { token } => {
+                Diagnostic::at_token(&token, "Invalid assignment target.")
+            }
+        }
+    }
+}
+
+impl From<ScanError> for Diagnostic {
+    fn from(err: ScanError) -> Self {
+        use ScanError::*;
+        match err {
+            UnexpectedCharacter { offset, ch } => {
+                Diagnostic::error(offset, &format!("Unexpected character '{}'.", ch))
+            }
+            UnterminatedString { offset } => Diagnostic::error(offset, "Unterminated string."),
+            UnterminatedComment { offset } => Diagnostic::error(offset, "Unterminated comment."),
+        }
+    }
+}
+
+impl From<RuntimeError> for Diagnostic {
+    fn from(err: RuntimeError) -> Self {
+        use RuntimeError::*;
+        match err {
+            OperandMustBeNumber { operator } => {
+                Diagnostic::at_token(&operator, "Operand must be a number.")
+            }
+            OperandsMustBeNumbers { operator } => {
+                Diagnostic::at_token(&operator, "Operands must be numbers.")
+            }
+            OperandsMustBeNumbersOrStrings { operator } => {
+                Diagnostic::at_token(&operator, "Operands must be two numbers or two strings.")
+            }
+            UndefinedVariable {
+                name,
+                suggestion: Some(suggestion),
+            } => Diagnostic::at_token(
+                &name,
+                &format!(
+                    "Undefined variable '{}'. Did you mean '{}'?",
+                    name.lexeme, suggestion
+                ),
+            ),
+            UndefinedVariable {
+                name,
+                suggestion: None,
+            } => Diagnostic::at_token(&name, &format!("Undefined variable '{}'.", name.lexeme)),
+            NotCallable { paren } => Diagnostic::at_token(&paren, "Can only call functions."),
+            WrongArity {
+                paren,
+                expected,
+                got,
+            } => Diagnostic::at_token(
+                &paren,
+                &format!("Expected {} arguments but got {}.", expected, got),
+            ),
+            InvalidArgument { paren, message } => Diagnostic::at_token(&paren, &message),
+            Exit { .. } => unreachable!("exit is handled before errors are reported"),
+        }
+    }
+}
+
+impl From<lint::Warning> for Diagnostic {
+    fn from(warning: lint::Warning) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            lint: Some(warning.lint),
+            ..Diagnostic::error(warning.offset, &warning.message)
+        }
+    }
+}
+
+/// Apply every edit to `source`. Edits are expected not to overlap; inserts
+/// at the same offset end up in the order they were given.
+pub fn apply_edits(source: &str, edits: &[Edit]) -> String {
+    let mut edits: Vec<(usize, &Edit)> = edits.iter().enumerate().collect();
+    // Back to front, so earlier offsets stay valid as we go. At the same
+    // offset the later edit goes in first, leaving the earlier one before it
+    edits.sort_by_key(|&(i, edit)| std::cmp::Reverse((edit.span.offset, i)));
+
+    let mut fixed = source.to_string();
+    for (_, edit) in edits {
+        let start = edit.span.offset.min(fixed.len());
+        let end = (edit.span.offset + edit.span.len).min(fixed.len());
+        fixed.replace_range(start..end, &edit.replacement);
+    }
+
+    fixed
+}
+
+/// A unified diff between two versions of a file, as a single hunk covering
+/// everything between the unchanged leading and trailing lines.
+pub fn diff(file_name: &str, before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let removed = &old[prefix..old.len() - suffix];
+    let added = &new[prefix..new.len() - suffix];
+
+    // An empty side starts at the line before the hunk, as in `diff -u`
+    let start = |len: usize| if len == 0 { prefix } else { prefix + 1 };
+    let mut out = format!("--- {file_name}\n+++ {file_name}\n");
+    out.push_str(&format!(
+        "@@ -{},{} +{},{} @@\n",
+        start(removed.len()),
+        removed.len(),
+        start(added.len()),
+        added.len()
+    ));
+    for line in removed {
+        out.push_str(&format!("-{line}\n"));
+    }
+    for line in added {
+        out.push_str(&format!("+{line}\n"));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuLox;
+
+    /// Whether the first diagnostic for `source` has a fix `rulox fix` would
+    /// apply, and what the source looks like with it applied.
+    fn first_fix(source: &str) -> (bool, String) {
+        let (_, diagnostics) = RuLox::analyze(source, false);
+        let fix = diagnostics
+            .into_iter()
+            .find_map(|diagnostic| diagnostic.fix)
+            .expect("source should have a fix");
+        (fix.machine_applicable, apply_edits(source, &fix.edits))
+    }
+
+    #[test]
+    fn semicolons_are_fixed_where_a_statement_ends() {
+        assert_eq!(
+            first_fix("print 1\nprint 2;"),
+            (true, "print 1;\nprint 2;".to_string())
+        );
+        assert_eq!(first_fix("print 1"), (true, "print 1;".to_string()));
+        assert_eq!(first_fix("print 1 }"), (true, "print 1; }".to_string()));
+    }
+
+    #[test]
+    fn guesses_are_only_offered_as_help() {
+        for (source, suggested) in [
+            ("print 1 \"a\";", "print 1; \"a\";"),
+            ("whiel (x) print x;", "while (x) print x;"),
+            ("var x = 1; printer x;", "var x = 1; print x;"),
+            ("print (1;", "print (1);"),
+            ("print f(1;", "print f(1);"),
+        ] {
+            assert_eq!(
+                first_fix(source),
+                (false, suggested.to_string()),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn edits_apply_back_to_front() {
+        let edits = [
+            Edit::insert(0, "print "),
+            Edit {
+                span: Span { offset: 2, len: 1 },
+                replacement: "*".to_string(),
+            },
+            Edit::insert(5, ";"),
+        ];
+        assert_eq!(apply_edits("1 + 2", &edits), "print 1 * 2;");
+    }
+
+    #[test]
+    fn inserts_at_one_offset_keep_their_order() {
+        let edits = [Edit::insert(8, ")"), Edit::insert(8, ";")];
+        assert_eq!(apply_edits("print (1", &edits), "print (1);");
+    }
+
+    #[test]
+    fn edits_use_byte_offsets() {
+        // "é" is two bytes, so the ';' after it is at offset 9
+        let source = "print \"é\"";
+        let edits = [Edit::insert(source.len(), ";")];
+        assert_eq!(apply_edits(source, &edits), "print \"é\";");
+        let edits = [Edit {
+            span: Span {
+                offset: 7,
+                len: "é".len(),
+            },
+            replacement: "e".to_string(),
+        }];
+        assert_eq!(apply_edits(source, &edits), "print \"e\"");
+    }
+
+    #[test]
+    fn diff_is_one_hunk_around_the_changes() {
+        let before = "var a = 1;\nprint (a;\nprint a\nprint 3;\n";
+        let after = "var a = 1;\nprint (a);\nprint a;\nprint 3;\n";
+        assert_eq!(
+            diff("f.lox", before, after),
+            "--- f.lox\n+++ f.lox\n@@ -2,2 +2,2 @@\n\
+             -print (a;\n-print a\n+print (a);\n+print a;\n"
+        );
+
+        // Pure insertions count from the line before, like diff -u
+        assert_eq!(
+            diff("f.lox", "a;\nb;\n", "a;\nnew;\nb;\n"),
+            "--- f.lox\n+++ f.lox\n@@ -1,0 +2,1 @@\n+new;\n"
+        );
+    }
+}
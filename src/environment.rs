@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use crate::{interpreter::RuntimeError, suggest, token::Token, value::Value};
+
+#[derive(Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+}
+
+impl Environment {
+    /// Bind a name, replacing any existing binding. Redefining globals is
+    /// allowed so the REPL can `var` the same name twice.
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
+        match self.values.get(&name.lexeme) {
+            Some(value) => Ok(value.clone()),
+            None => Err(self.undefined(name)),
+        }
+    }
+
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        match self.values.get_mut(&name.lexeme) {
+            Some(slot) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(self.undefined(name)),
+        }
+    }
+
+    pub fn bindings(&self) -> Vec<(String, Value)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    fn undefined(&self, name: &Token) -> RuntimeError {
+        let suggestion = suggest::closest(&name.lexeme, self.values.keys().map(String::as_str))
+            .map(String::from);
+        RuntimeError::UndefinedVariable {
+            name: name.clone(),
+            suggestion,
+        }
+    }
+}
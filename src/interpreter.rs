@@ -0,0 +1,458 @@
+use crate::{
+    ast::{
+        expr::Expr,
+        stmt::Stmt,
+        visitor::{StmtVisitorMut, VisitorMut},
+    },
+    environment::Environment,
+    native::NATIVES,
+    token::{Literal, Token, TokenType},
+    value::Value,
+};
+
+#[derive(Debug)]
+pub enum RuntimeError {
+    OperandMustBeNumber {
+        operator: Token,
+    },
+    OperandsMustBeNumbers {
+        operator: Token,
+    },
+    OperandsMustBeNumbersOrStrings {
+        operator: Token,
+    },
+    UndefinedVariable {
+        name: Token,
+        /// The closest defined name, if one is near enough to be a typo.
+        suggestion: Option<String>,
+    },
+    NotCallable {
+        paren: Token,
+    },
+    WrongArity {
+        paren: Token,
+        expected: usize,
+        got: usize,
+    },
+    /// A native function was handed an argument it can't use.
+    InvalidArgument {
+        paren: Token,
+        message: String,
+    },
+    /// Not an error: the script called `exit`, and unwinds with this.
+    Exit {
+        code: i32,
+    },
+}
+
+/// A call in progress: the function called and the ')' of the call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    pub call_site: Token,
+}
+
+/// How many frames a backtrace keeps at each end before eliding the middle.
+const BACKTRACE_EDGE: usize = 8;
+
+/// A backtrace for the frames a runtime error escaped from, innermost first,
+/// one `at` line per frame. `location` says where a call site is.
+pub fn backtrace(frames: &[Frame], location: impl Fn(&Token) -> String) -> String {
+    let line = |frame: &Frame| {
+        format!(
+            "    at {}() ({})\n",
+            frame.function,
+            location(&frame.call_site)
+        )
+    };
+
+    if frames.len() <= 2 * BACKTRACE_EDGE {
+        return frames.iter().map(line).collect();
+    }
+    let mut out: String = frames[..BACKTRACE_EDGE].iter().map(line).collect();
+    out.push_str(&format!(
+        "    ... {} more calls ...\n",
+        frames.len() - 2 * BACKTRACE_EDGE
+    ));
+    out.extend(frames[frames.len() - BACKTRACE_EDGE..].iter().map(line));
+    out
+}
+
+/// Tree-walking interpreter. It holds on to its globals between calls to
+/// `interpret`, so the REPL can keep one around for the whole session.
+pub struct Interpreter {
+    environment: Environment,
+    /// Calls in progress, outermost first. A runtime error leaves them in
+    /// place, so `take_frames` can say where it happened.
+    frames: Vec<Frame>,
+    /// Command-line arguments for the script, as seen by `args` and `arg`.
+    script_args: Vec<String>,
+}
+
+impl Interpreter {
+    pub fn new(script_args: Vec<String>) -> Self {
+        let mut environment = Environment::default();
+        for native in NATIVES {
+            environment.define(native.name, Value::Native(native));
+        }
+
+        Interpreter {
+            environment,
+            frames: Vec::new(),
+            script_args,
+        }
+    }
+
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
+        for statement in statements {
+            self.execute(statement)?;
+        }
+        Ok(())
+    }
+
+    /// Every global and its current value, sorted by name.
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let mut globals = self.environment.bindings();
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+        globals
+    }
+
+    /// The calls the last runtime error escaped from, innermost first,
+    /// leaving the stack empty for the next run.
+    pub fn take_frames(&mut self) -> Vec<Frame> {
+        let mut frames = std::mem::take(&mut self.frames);
+        frames.reverse();
+        frames
+    }
+
+    pub fn script_args(&self) -> &[String] {
+        &self.script_args
+    }
+
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        expr.accept_mut(self)
+    }
+
+    fn execute(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
+        stmt.accept_mut(self)
+    }
+
+    fn number_operand(&self, operator: &Token, operand: &Value) -> Result<f64, RuntimeError> {
+        match operand {
+            Value::Number(n) => Ok(*n),
+            _ => Err(RuntimeError::OperandMustBeNumber {
+                operator: operator.clone(),
+            }),
+        }
+    }
+
+    fn number_operands(
+        &self,
+        operator: &Token,
+        left: &Value,
+        right: &Value,
+    ) -> Result<(f64, f64), RuntimeError> {
+        match (left, right) {
+            (Value::Number(l), Value::Number(r)) => Ok((*l, *r)),
+            _ => Err(RuntimeError::OperandsMustBeNumbers {
+                operator: operator.clone(),
+            }),
+        }
+    }
+}
+
+impl VisitorMut<Result<Value, RuntimeError>> for Interpreter {
+    fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<Value, RuntimeError> {
+        let value = self.evaluate(value)?;
+        self.environment.assign(name, value.clone())?;
+        Ok(value)
+    }
+
+    fn visit_binary_expr(
+        &mut self,
+        left: &Expr,
+        right: &Expr,
+        operator: &Token,
+    ) -> Result<Value, RuntimeError> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+
+        use TokenType::*;
+        let value = match operator.token_type {
+            Minus => {
+                let (l, r) = self.number_operands(operator, &left, &right)?;
+                Value::Number(l - r)
+            }
+            Slash => {
+                let (l, r) = self.number_operands(operator, &left, &right)?;
+                Value::Number(l / r)
+            }
+            Star => {
+                let (l, r) = self.number_operands(operator, &left, &right)?;
+                Value::Number(l * r)
+            }
+            Plus => match (left, right) {
+                (Value::Number(l), Value::Number(r)) => Value::Number(l + r),
+                (Value::Str(l), Value::Str(r)) => Value::Str(l + &r),
+                _ => {
+                    return Err(RuntimeError::OperandsMustBeNumbersOrStrings {
+                        operator: operator.clone(),
+                    })
+                }
+            },
+            Greater => {
+                let (l, r) = self.number_operands(operator, &left, &right)?;
+                Value::Bool(l > r)
+            }
+            GreaterEqual => {
+                let (l, r) = self.number_operands(operator, &left, &right)?;
+                Value::Bool(l >= r)
+            }
+            Less => {
+                let (l, r) = self.number_operands(operator, &left, &right)?;
+                Value::Bool(l < r)
+            }
+            LessEqual => {
+                let (l, r) = self.number_operands(operator, &left, &right)?;
+                Value::Bool(l <= r)
+            }
+            BangEqual => Value::Bool(left != right),
+            EqualEqual => Value::Bool(left == right),
+            _ => unreachable!("not a binary operator: {:?}", operator.token_type),
+        };
+
+        Ok(value)
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<Value, RuntimeError> {
+        let callee = self.evaluate(callee)?;
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Value::Native(native) = callee else {
+            return Err(RuntimeError::NotCallable {
+                paren: paren.clone(),
+            });
+        };
+        if arguments.len() != native.arity {
+            return Err(RuntimeError::WrongArity {
+                paren: paren.clone(),
+                expected: native.arity,
+                got: arguments.len(),
+            });
+        }
+
+        self.frames.push(Frame {
+            function: native.name.to_string(),
+            call_site: paren.clone(),
+        });
+        let value = (native.function)(self, paren, &arguments)?;
+        self.frames.pop();
+        Ok(value)
+    }
+
+    fn visit_grouping_expr(
+        &mut self,
+        _left_paren: &Token,
+        expression: &Expr,
+        _right_paren: &Token,
+    ) -> Result<Value, RuntimeError> {
+        self.evaluate(expression)
+    }
+
+    fn visit_literal_expr(
+        &mut self,
+        value: &Literal,
+        _token: &Token,
+    ) -> Result<Value, RuntimeError> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<Value, RuntimeError> {
+        let right = self.evaluate(right)?;
+
+        match operator.token_type {
+            TokenType::Minus => Ok(Value::Number(-self.number_operand(operator, &right)?)),
+            TokenType::Bang => Ok(Value::Bool(!right.is_truthy())),
+            _ => unreachable!("not a unary operator: {:?}", operator.token_type),
+        }
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Result<Value, RuntimeError> {
+        self.environment.get(name)
+    }
+}
+
+impl StmtVisitorMut<Result<(), RuntimeError>> for Interpreter {
+    fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), RuntimeError> {
+        self.evaluate(expression)?;
+        Ok(())
+    }
+
+    fn visit_print_stmt(
+        &mut self,
+        _keyword: &Token,
+        expression: &Expr,
+    ) -> Result<(), RuntimeError> {
+        let value = self.evaluate(expression)?;
+        println!("{}", value);
+        Ok(())
+    }
+
+    fn visit_var_stmt(
+        &mut self,
+        _keyword: &Token,
+        name: &Token,
+        initializer: &Option<Expr>,
+    ) -> Result<(), RuntimeError> {
+        let value = match initializer {
+            Some(initializer) => self.evaluate(initializer)?,
+            None => Value::Nil,
+        };
+
+        self.environment.define(&name.lexeme, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuLox;
+
+    fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+        let (statements, _) = RuLox::analyze(source, false);
+        interpreter.interpret(&statements.expect("source should parse"))
+    }
+
+    /// The value of a lone expression, as the REPL would echo it.
+    fn eval(interpreter: &mut Interpreter, source: &str) -> Result<Value, RuntimeError> {
+        let (statements, _) = RuLox::analyze(source, true);
+        match &statements.expect("source should parse")[..] {
+            [Stmt::Expression { expression }] => interpreter.evaluate(expression),
+            _ => panic!("{:?} should be one expression", source),
+        }
+    }
+
+    #[test]
+    fn arithmetic_follows_precedence() {
+        let mut interpreter = Interpreter::new(Vec::new());
+        assert_eq!(
+            eval(&mut interpreter, "1 + 2 * 3 - 8 / 4").unwrap(),
+            Value::Number(5.0)
+        );
+        assert_eq!(
+            eval(&mut interpreter, "-(1 + 2) < 0 == !false").unwrap(),
+            Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn plus_joins_strings() {
+        let mut interpreter = Interpreter::new(Vec::new());
+        assert_eq!(
+            eval(&mut interpreter, "\"rul\" + \"ox\"").unwrap(),
+            Value::Str("rulox".to_string())
+        );
+    }
+
+    #[test]
+    fn operands_of_the_wrong_type_are_errors() {
+        let mut interpreter = Interpreter::new(Vec::new());
+        assert!(matches!(
+            eval(&mut interpreter, "1 + \"a\""),
+            Err(RuntimeError::OperandsMustBeNumbersOrStrings { .. })
+        ));
+        assert!(matches!(
+            eval(&mut interpreter, "true * 2"),
+            Err(RuntimeError::OperandsMustBeNumbers { .. })
+        ));
+        assert!(matches!(
+            eval(&mut interpreter, "-nil"),
+            Err(RuntimeError::OperandMustBeNumber { .. })
+        ));
+    }
+
+    #[test]
+    fn undefined_variables_suggest_the_closest_global() {
+        let mut interpreter = Interpreter::new(Vec::new());
+        run(&mut interpreter, "var count = 1;").unwrap();
+
+        let err = run(&mut interpreter, "cuont = 2;").unwrap_err();
+        assert!(matches!(
+            err,
+            RuntimeError::UndefinedVariable { name, suggestion: Some(suggestion) }
+                if name.lexeme == "cuont" && suggestion == "count"
+        ));
+        let err = eval(&mut interpreter, "zebra").unwrap_err();
+        assert!(matches!(
+            err,
+            RuntimeError::UndefinedVariable {
+                suggestion: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn globals_persist_between_runs() {
+        let mut interpreter = Interpreter::new(Vec::new());
+        run(&mut interpreter, "var a = 1;").unwrap();
+        run(&mut interpreter, "a = a + 1;").unwrap();
+
+        assert_eq!(eval(&mut interpreter, "a").unwrap(), Value::Number(2.0));
+        assert!(interpreter
+            .globals()
+            .contains(&("a".to_string(), Value::Number(2.0))));
+    }
+
+    #[test]
+    fn failed_calls_leave_their_frames_for_the_backtrace() {
+        let mut interpreter = Interpreter::new(Vec::new());
+        run(&mut interpreter, "print args();").unwrap();
+        assert!(interpreter.take_frames().is_empty());
+
+        let err = run(&mut interpreter, "print args() + arg(1.5);").unwrap_err();
+        assert!(matches!(err, RuntimeError::InvalidArgument { .. }));
+        let frames = interpreter.take_frames();
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| (frame.function.as_str(), frame.call_site.offset))
+                .collect::<Vec<_>>(),
+            [("arg", 22)]
+        );
+        assert!(interpreter.take_frames().is_empty());
+    }
+
+    #[test]
+    fn deep_backtraces_elide_the_middle() {
+        let frames: Vec<Frame> = (0..20)
+            .map(|depth| Frame {
+                function: format!("f{}", depth),
+                call_site: Token::new(TokenType::RightParen, ")".to_string(), None, depth),
+            })
+            .collect();
+        let trace = backtrace(&frames, |call_site| {
+            format!("test.lox:{}", call_site.offset)
+        });
+        let lines: Vec<&str> = trace.lines().collect();
+
+        assert_eq!(lines.len(), 2 * BACKTRACE_EDGE + 1);
+        assert_eq!(lines[0], "    at f0() (test.lox:0)");
+        assert_eq!(lines[BACKTRACE_EDGE], "    ... 4 more calls ...");
+        assert_eq!(lines[lines.len() - 1], "    at f19() (test.lox:19)");
+
+        assert_eq!(
+            backtrace(&frames[..2], |_| String::new()).lines().count(),
+            2
+        );
+    }
+}
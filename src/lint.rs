@@ -0,0 +1,210 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    ast::arena::{Ast, Node, NodeId},
+    token::{Literal, Token, TokenType},
+};
+
+/// Every warning the static passes know how to emit. The CLI refers to them by
+/// `name()`, e.g. `-D mixed_type_comparison`. Lints about unused or shadowed
+/// locals, code after `return` and constant conditions need blocks, functions
+/// and control flow to look at, so they'll join once the parser has them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// `==` or `!=` between literals of different types, which is always false
+    /// (or always true).
+    MixedTypeComparison,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 1] = [Lint::MixedTypeComparison];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::MixedTypeComparison => "mixed_type_comparison",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// The level each lint is reported at. Anything not configured is a warning.
+#[derive(Debug, Default)]
+pub struct LintLevels {
+    levels: HashMap<Lint, LintLevel>,
+}
+
+impl LintLevels {
+    pub fn set(&mut self, lint: Lint, level: LintLevel) {
+        self.levels.insert(lint, level);
+    }
+
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.levels.get(&lint).copied().unwrap_or(LintLevel::Warn)
+    }
+}
+
+#[derive(Debug)]
+pub struct Warning {
+    pub lint: Lint,
+    pub offset: usize,
+    pub message: String,
+}
+
+/// Checks every node of a program's `Ast` and collects warnings.
+#[derive(Default)]
+pub struct Linter {
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    pub fn check(mut self, ast: &Ast) -> Vec<Warning> {
+        // The literal each node is made of, looking through any grouping.
+        // Children come before their parents, so one pass fills it in.
+        let mut literals: HashMap<NodeId, &Literal> = HashMap::new();
+        for (id, node) in ast.iter() {
+            match node {
+                Node::Literal { value, .. } => {
+                    literals.insert(id, value);
+                }
+                Node::Grouping { expression, .. } => {
+                    if let Some(&value) = literals.get(expression) {
+                        literals.insert(id, value);
+                    }
+                }
+                Node::Binary {
+                    left,
+                    right,
+                    operator,
+                } => self.check_comparison(
+                    literals.get(left).copied(),
+                    literals.get(right).copied(),
+                    operator,
+                ),
+                _ => {}
+            }
+        }
+        self.warnings
+    }
+
+    fn check_comparison(
+        &mut self,
+        left: Option<&Literal>,
+        right: Option<&Literal>,
+        operator: &Token,
+    ) {
+        if !matches!(
+            operator.token_type,
+            TokenType::EqualEqual | TokenType::BangEqual
+        ) {
+            return;
+        }
+        if let (Some(l), Some(r)) = (left, right) {
+            let (l_type, r_type) = (Self::literal_type(l), Self::literal_type(r));
+            if l_type != r_type {
+                self.warn(
+                    Lint::MixedTypeComparison,
+                    operator.offset,
+                    format!(
+                        "comparing a {} with a {} using '{}' is always {}.",
+                        l_type,
+                        r_type,
+                        operator.lexeme,
+                        operator.token_type == TokenType::BangEqual
+                    ),
+                );
+            }
+        }
+    }
+
+    fn warn(&mut self, lint: Lint, offset: usize, message: String) {
+        self.warnings.push(Warning {
+            lint,
+            offset,
+            message,
+        });
+    }
+
+    fn literal_type(value: &Literal) -> &'static str {
+        match value {
+            Literal::Number(_) => "number",
+            Literal::Str(_) => "string",
+            Literal::Bool(_) => "boolean",
+            Literal::Nil => "nil",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cli::EX_DATAERR, RuLox};
+
+    fn lint(source: &str) -> Vec<String> {
+        let (statements, _) = RuLox::analyze(source, false);
+        let ast = Ast::lower(&statements.expect("source should parse"));
+        Linter::default()
+            .check(&ast)
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect()
+    }
+
+    fn failure_code(levels: LintLevels, source: &str) -> Option<i32> {
+        let mut rulox = RuLox::new(levels, Vec::new());
+        let file = rulox.source_map.add("test.lox", source.to_string());
+        rulox.run(file, false);
+        rulox.failure_code()
+    }
+
+    #[test]
+    fn comparing_literals_of_different_types_warns() {
+        assert_eq!(
+            lint("print 1 == \"a\";\nprint (nil) != true;\n"),
+            [
+                "comparing a number with a string using '==' is always false.",
+                "comparing a nil with a boolean using '!=' is always true.",
+            ]
+        );
+        assert!(lint("print 1 == 2;\nprint a == \"a\";\nprint 1 < \"a\";\n").is_empty());
+    }
+
+    #[test]
+    fn lints_are_warnings_unless_configured() {
+        let mut levels = LintLevels::default();
+        assert_eq!(levels.level(Lint::MixedTypeComparison), LintLevel::Warn);
+
+        levels.set(Lint::MixedTypeComparison, LintLevel::Allow);
+        assert_eq!(levels.level(Lint::MixedTypeComparison), LintLevel::Allow);
+        assert_eq!(
+            Lint::from_name("mixed_type_comparison"),
+            Some(Lint::MixedTypeComparison)
+        );
+        assert_eq!(Lint::from_name("unused_variables"), None);
+    }
+
+    #[test]
+    fn denied_lints_fail_the_run() {
+        let source = "1 == \"a\";\n";
+        assert_eq!(failure_code(LintLevels::default(), source), None);
+
+        let mut levels = LintLevels::default();
+        levels.set(Lint::MixedTypeComparison, LintLevel::Deny);
+        assert_eq!(failure_code(levels, source), Some(EX_DATAERR));
+    }
+}
@@ -1,148 +1,727 @@
-use std::io;
+use std::io::{self, Read};
 use std::{
-    env,
-    fs::File,
-    io::{BufRead, BufReader, Read, Write},
-    process,
+    env, fs,
+    io::Write,
+    process, thread,
+    time::{Duration, Instant, SystemTime},
 };
 
+use repl::{Command, ReplHelper};
+use rustyline::{error::ReadlineError, history::FileHistory, Config, Editor};
+
+use ast::arena::Ast;
 use ast::ast_printer::AstPrinter;
-use ast::expr::Expr;
-use token::{Token, TokenLiteral, TokenType};
+use ast::dot_printer::DotPrinter;
+#[cfg(feature = "serde")]
+use ast::json::Program;
+use ast::rpn_printer::RpnPrinter;
+use ast::stmt::Stmt;
+use ast::tree_printer::TreePrinter;
+use ast::unparser::Unparser;
+use cli::{
+    AstFormat, Cli, Subcommand, TokenFormat, EX_DATAERR, EX_IOERR, EX_NOINPUT, EX_SOFTWARE,
+    EX_USAGE,
+};
+use desugar::desugar;
+use diagnostic::{apply_edits, Diagnostic, Edit, Fix, Severity};
+use interpreter::{Interpreter, RuntimeError};
+use lint::{LintLevel, LintLevels, Linter};
+use source_map::{FileId, SourceMap};
+use token::{Literal, Token, TokenType};
+
+/// Upper bound on how many rounds of edits `rulox fix` will apply, in case a
+/// fix keeps producing new fixable errors.
+const MAX_FIX_ROUNDS: usize = 64;
+
+/// How often `rulox run --watch` checks whether the script has changed.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
 
 mod ast;
+mod cli;
+mod desugar;
+mod diagnostic;
+mod environment;
+mod interpreter;
+mod lint;
+mod native;
+mod parser;
+mod repl;
 mod scanner;
+mod source_map;
+mod suggest;
 mod token;
-mod parser;
+mod value;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    println!("{:?}", args);
-    let mut rulox = RuLox::new();
 
-    if args.len() > 2 {
-        println!("Usage: rulox [script]");
-        process::exit(64);
-    } else if args.len() == 2 {
-        let _r = rulox.run_file(&args[1]);
-    } else {
-        let _r = rulox.run_prompt();
+    let cli = match Cli::parse(&args[1..]) {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            process::exit(EX_USAGE);
+        }
+    };
+    let mut rulox = RuLox::new(cli.lint_levels, cli.script_args);
+
+    match cli.subcommand {
+        Subcommand::Run { script } => rulox.run_file(&script),
+        Subcommand::Watch { script } => rulox.watch_file(&script),
+        #[cfg(feature = "serde")]
+        Subcommand::RunAst { script } => rulox.run_ast_file(&script),
+        Subcommand::Eval { source } => {
+            let file = rulox.source_map.add("<eval>", source);
+            rulox.run_script(file);
+        }
+        Subcommand::Repl => {
+            if let Err(err) = rulox.run_prompt() {
+                eprintln!("{}", err);
+                process::exit(EX_IOERR);
+            }
+            if let Some(code) = rulox.exit_code {
+                process::exit(code);
+            }
+        }
+        Subcommand::Tokens { script, format } => rulox.print_tokens(&script, format),
+        Subcommand::Ast { script, format } => rulox.print_ast(&script, format),
+        Subcommand::Check { script } => rulox.check_file(&script),
+        Subcommand::Fix { script, dry_run } => {
+            if let Err(err) = rulox.fix_file(&script, dry_run) {
+                eprintln!("Cannot fix {}: {}", script, err);
+                process::exit(EX_IOERR);
+            }
+        }
+        Subcommand::Fmt { script } => rulox.format_file(&script),
+        Subcommand::Desugar { script } => rulox.desugar_file(&script),
+        Subcommand::Help => println!("{}", cli::USAGE),
+        Subcommand::Version => println!("rulox {}", env!("CARGO_PKG_VERSION")),
     }
 }
 
-fn print_ast() {
-    let expression = Expr::Binary {
-        left: Box::new(Expr::Unary {
-            operator: Token::new(TokenType::Minus, "-".to_string(), None, 1),
-            right: Box::new(Expr::Literal {
-                value: Some(TokenLiteral::from_number(123f64)),
-            }),
-        }),
-        right: Box::new(Expr::Grouping {
-            expression: Box::new(Expr::Literal {
-                value: Some(TokenLiteral::from_number(45.67)),
-            }),
-        }),
-        operator: Token::new(TokenType::Star, "*".to_string(), None, 1),
-    };
-    println!("Expression: \n{}", AstPrinter {}.print(expression));
+/// `s` as a JSON string literal, quotes included.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
 }
 
 struct RuLox {
     had_error: bool,
+    had_runtime_error: bool,
+    /// Set when the program calls `exit`.
+    exit_code: Option<i32>,
+    lint_levels: LintLevels,
+    source_map: SourceMap,
+    interpreter: Interpreter,
 }
 
 impl RuLox {
-    fn new() -> Self {
-        RuLox { had_error: false }
+    fn new(lint_levels: LintLevels, script_args: Vec<String>) -> Self {
+        RuLox {
+            had_error: false,
+            had_runtime_error: false,
+            exit_code: None,
+            lint_levels,
+            source_map: SourceMap::default(),
+            interpreter: Interpreter::new(script_args),
+        }
     }
 
-    fn run_file(&mut self, file_name: &str) -> Result<(), std::io::Error> {
-        let Ok(file) = File::open(file_name) else {
-            panic!("Cannot open {}", file_name);
+    /// Read a script into the source map, exiting if it can't be read. A
+    /// script named `-` is the whole of stdin.
+    fn load(&mut self, file_name: &str) -> FileId {
+        let (name, source) = if file_name == "-" {
+            let mut source = String::new();
+            (
+                "<stdin>",
+                io::stdin().read_to_string(&mut source).map(|_| source),
+            )
+        } else {
+            (file_name, fs::read_to_string(file_name))
         };
 
-        let mut reader = BufReader::new(file);
-        let mut buffer = String::new();
-        reader.read_to_string(&mut buffer)?;
-        self.run(&buffer);
+        match source {
+            Ok(source) => self.source_map.add(name, source),
+            Err(err) => {
+                eprintln!("Cannot open {}: {}", name, err);
+                process::exit(EX_NOINPUT);
+            }
+        }
+    }
+
+    fn run_file(&mut self, file_name: &str) {
+        let file = self.load(file_name);
+        self.run_script(file);
+    }
+
+    /// Run a whole script, exiting with a failure code if it went wrong.
+    fn run_script(&mut self, file: FileId) {
+        self.run(file, false);
+        self.exit_on_failure();
+    }
+
+    /// `rulox run --from-ast`: run a syntax tree written as JSON, skipping the
+    /// scanner and parser. Errors point into the source the tree came with, if
+    /// it has one.
+    #[cfg(feature = "serde")]
+    fn run_ast_file(&mut self, file_name: &str) {
+        let json = self.load(file_name);
+        let program = match Program::from_json(self.source_map.source(json)) {
+            Ok(program) => program,
+            Err(err) => {
+                eprintln!("Invalid syntax tree in {}: {}", file_name, err);
+                process::exit(EX_DATAERR);
+            }
+        };
+
+        let file = self
+            .source_map
+            .add(file_name, program.source.unwrap_or_default());
+        self.execute(file, program.statements, false);
+        self.exit_on_failure();
+    }
+
+    /// Exit with a failure code if the script went wrong, or asked to exit.
+    fn exit_on_failure(&self) {
+        if let Some(code) = self.failure_code() {
+            process::exit(code);
+        }
+    }
+
+    /// The code to exit with, if the script went wrong or asked to exit.
+    fn failure_code(&self) -> Option<i32> {
+        if self.exit_code.is_some() {
+            return self.exit_code;
+        }
+        // Runtime errors are reported like any other, so check for them first
+        if self.had_runtime_error {
+            return Some(EX_SOFTWARE);
+        }
+        self.had_error.then_some(EX_DATAERR)
+    }
+
+    /// `rulox run --watch`: run a script, then run it again from a clean slate
+    /// each time it, or any file it loaded, is modified. Runs until killed.
+    fn watch_file(&mut self, file_name: &str) -> ! {
+        loop {
+            // Clear the screen and move the cursor to the top left
+            print!("\x1b[2J\x1b[H");
+            let _ = io::stdout().flush();
+
+            let script_args = self.interpreter.script_args().to_vec();
+            self.interpreter = Interpreter::new(script_args);
+            self.source_map = SourceMap::default();
+            self.had_error = false;
+            self.had_runtime_error = false;
+            self.exit_code = None;
+
+            match fs::read_to_string(file_name) {
+                Ok(source) => {
+                    let file = self.source_map.add(file_name, source);
+                    self.run(file, false);
+                    if let Some(code) = self.exit_code {
+                        println!("[exited with code {}]", code);
+                    }
+                }
+                // Editors sometimes replace a file rather than writing to it,
+                // so it may be back by the next check
+                Err(err) => eprintln!("Cannot open {}: {}", file_name, err),
+            }
+
+            let mut watched: Vec<String> = self.source_map.names().map(String::from).collect();
+            if watched.is_empty() {
+                watched.push(file_name.to_string());
+            }
+            eprintln!("[watching {} for changes]", watched.join(", "));
+
+            let modified = |files: &[String]| -> Vec<Option<SystemTime>> {
+                files
+                    .iter()
+                    .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+                    .collect()
+            };
+            let before = modified(&watched);
+            while modified(&watched) == before {
+                thread::sleep(WATCH_INTERVAL);
+            }
+        }
+    }
+
+    /// `rulox tokens`: dump what the scanner makes of a script.
+    fn print_tokens(&mut self, file_name: &str, format: TokenFormat) {
+        let file = self.load(file_name);
+        let mut tokens = Vec::new();
+        for token_res in scanner::Scanner::new(self.source_map.source(file)) {
+            match token_res {
+                Ok(token) => {
+                    let is_eof = token.token_type == TokenType::Eof;
+                    tokens.push(token);
+                    if is_eof {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    self.emit(file, err.into());
+                    process::exit(EX_DATAERR);
+                }
+            }
+        }
+
+        match format {
+            TokenFormat::Text => {
+                for token in tokens {
+                    println!("{}", token);
+                }
+            }
+            TokenFormat::Json => {
+                let entries: Vec<String> = tokens
+                    .iter()
+                    .map(|token| {
+                        let location = self.source_map.location(file, token.offset);
+                        let literal = match &token.literal {
+                            None => "null".to_string(),
+                            Some(Literal::Str(val)) => json_string(val),
+                            Some(Literal::Nil) => "null".to_string(),
+                            Some(literal) => literal.to_string(),
+                        };
+                        format!(
+                            "  {{\"type\": {}, \"lexeme\": {}, \"literal\": {}, \"offset\": {}, \"line\": {}, \"column\": {}}}",
+                            json_string(&format!("{:?}", token.token_type)),
+                            json_string(&token.lexeme),
+                            literal,
+                            token.offset,
+                            location.line,
+                            location.column
+                        )
+                    })
+                    .collect();
+                println!("[\n{}\n]", entries.join(",\n"));
+            }
+        }
+    }
+
+    /// `rulox ast`: print the syntax tree of each statement in a script.
+    fn print_ast(&mut self, file_name: &str, format: AstFormat) {
+        let file = self.load(file_name);
+        let (statements, diagnostics) = Self::analyze(self.source_map.source(file), false);
+        for diagnostic in diagnostics {
+            self.emit(file, diagnostic);
+        }
+        let Some(statements) = statements else {
+            process::exit(EX_DATAERR);
+        };
+
+        match format {
+            AstFormat::Sexpr => {
+                for statement in &statements {
+                    println!("{}", AstPrinter {}.print_stmt(statement));
+                }
+            }
+            AstFormat::Rpn => {
+                for statement in &statements {
+                    println!("{}", RpnPrinter {}.print_stmt(statement));
+                }
+            }
+            AstFormat::Tree => {
+                print!(
+                    "{}",
+                    TreePrinter::new(&self.source_map, file).print(&statements)
+                )
+            }
+            AstFormat::Dot => print!("{}", DotPrinter {}.print(&Ast::lower(&statements))),
+            #[cfg(feature = "serde")]
+            AstFormat::Json => {
+                let program = Program {
+                    source: Some(self.source_map.source(file).to_string()),
+                    statements,
+                };
+                println!("{}", program.to_json());
+            }
+        }
+    }
+
+    /// `rulox fmt`: print a script the way the unparser lays it out.
+    fn format_file(&mut self, file_name: &str) {
+        let file = self.load(file_name);
+        let (statements, diagnostics) = Self::analyze(self.source_map.source(file), false);
+        // Lints are for `check`; only errors stop formatting
+        for diagnostic in diagnostics {
+            if diagnostic.severity == Severity::Error {
+                self.emit(file, diagnostic);
+            }
+        }
+        let Some(statements) = statements else {
+            process::exit(EX_DATAERR);
+        };
+
+        print!("{}", Unparser {}.unparse(&statements));
+    }
+
+    /// `rulox desugar`: print a script as the interpreter sees it, after every
+    /// desugaring pass.
+    fn desugar_file(&mut self, file_name: &str) {
+        let file = self.load(file_name);
+        let (statements, diagnostics) = Self::analyze(self.source_map.source(file), false);
+        for diagnostic in diagnostics {
+            if diagnostic.severity == Severity::Error {
+                self.emit(file, diagnostic);
+            }
+        }
+        let Some(statements) = statements else {
+            process::exit(EX_DATAERR);
+        };
+
+        print!("{}", Unparser {}.unparse(&desugar(statements)));
+    }
+
+    /// `rulox check`: report every diagnostic short of running the script.
+    fn check_file(&mut self, file_name: &str) {
+        let file = self.load(file_name);
+        let (_, diagnostics) = Self::analyze(self.source_map.source(file), false);
+        for diagnostic in diagnostics {
+            self.emit(file, diagnostic);
+        }
         if self.had_error {
-            process::exit(65);
+            process::exit(EX_DATAERR);
         }
-        Ok(())
     }
 
-    fn run_prompt(&mut self) -> Result<(), io::Error> {
-        let std_input = std::io::stdin();
-        let mut reader = BufReader::new(std_input);
-        let mut lock = std::io::stdout().lock();
+    fn run_prompt(&mut self) -> Result<(), ReadlineError> {
+        let config = Config::builder()
+            .max_history_size(1000)?
+            .history_ignore_dups(true)?
+            .build();
+        let mut editor: Editor<ReplHelper, FileHistory> = Editor::with_config(config)?;
+        editor.set_helper(Some(ReplHelper::default()));
+        // The natives are there before anything has run
+        self.refresh_completions(&mut editor);
+        let history = repl::history_path();
+        if let Some(path) = &history {
+            // There's no history the first time round
+            let _ = editor.load_history(path);
+        }
+
+        let mut buffer = String::new();
+        let mut entry = 1;
         loop {
-            print!("> ");
-            io::stdout().flush()?;
+            let prompt = if buffer.is_empty() { "> " } else { "... " };
+            match editor.readline(prompt) {
+                Ok(line) if buffer.is_empty() && line.trim_start().starts_with(':') => {
+                    editor.add_history_entry(line.trim())?;
+                    match Command::parse(&line) {
+                        Ok(command) => self.run_command(command, &mut entry),
+                        Err(message) => eprintln!("{}", message),
+                    }
+                    self.refresh_completions(&mut editor);
+                    if self.exit_code.is_some() {
+                        break;
+                    }
+                    continue;
+                }
+                Ok(line) => {
+                    buffer.push_str(&line);
+                    buffer.push('\n');
+                    if repl::is_incomplete(&buffer) {
+                        continue;
+                    }
+                }
+                // Ctrl-C throws away whatever has been typed so far
+                Err(ReadlineError::Interrupted) => {
+                    buffer.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(err),
+            }
 
-            let mut line = String::new();
-            let bytes_read = reader.read_line(&mut line)?;
-            if bytes_read == 0 {
+            let source = std::mem::take(&mut buffer);
+            editor.add_history_entry(source.trim_end())?;
+            let file = self.source_map.add(&format!("<repl:{}>", entry), source);
+            self.run(file, true);
+            entry += 1;
+            self.refresh_completions(&mut editor);
+            if self.exit_code.is_some() {
                 break;
             }
-            self.run(&line);
 
-            // Reset error flag
+            // Reset error flags
             self.had_error = false;
-            line.clear();
+            self.had_runtime_error = false;
         }
 
-        lock.flush()?;
+        if let Some(path) = &history {
+            editor.save_history(path)?;
+        }
         Ok(())
     }
 
-    fn run(&mut self, source: &str) {
-        use token::*;
-        let scanner = scanner::Scanner::new(source);
-        for token_res in scanner {
-            match token_res {
-                Ok(Token {
-                    token_type: TokenType::Eof,
-                    ..
-                }) => {
-                    break;
+    /// Let Tab complete whatever globals the session has defined so far.
+    fn refresh_completions(&self, editor: &mut Editor<ReplHelper, FileHistory>) {
+        if let Some(helper) = editor.helper_mut() {
+            let names = self.interpreter.globals().into_iter();
+            helper.set_globals(names.map(|(name, _)| name).collect());
+        }
+    }
+
+    /// Carry out a REPL meta-command. `entry` numbers the `<repl:N>` sources
+    /// so commands that scan or run input get diagnostics like any other entry.
+    fn run_command(&mut self, command: Command, entry: &mut usize) {
+        match command {
+            Command::Tokens(source) => {
+                let file = self.source_map.add(&format!("<repl:{}>", entry), source);
+                *entry += 1;
+                for token_res in scanner::Scanner::new(self.source_map.source(file)) {
+                    match token_res {
+                        Ok(Token {
+                            token_type: TokenType::Eof,
+                            ..
+                        }) => break,
+                        Ok(token) => println!("{}", token),
+                        Err(err) => {
+                            self.emit(file, err.into());
+                            break;
+                        }
+                    }
                 }
-                Ok(token) => {
-                    println!("{:?}", token);
+            }
+            Command::Ast(source) => {
+                let file = self.source_map.add(&format!("<repl:{}>", entry), source);
+                *entry += 1;
+                let (statements, diagnostics) = Self::analyze(self.source_map.source(file), true);
+                for diagnostic in diagnostics {
+                    self.emit(file, diagnostic);
                 }
-                Err(err) => {
-                    use scanner::ScanError::*;
-                    match err {
-                        UnexpectedCharacter { line, ch } => {
-                            self.error(
-                                line,
-                                format!("Unexpected chatacter '{}' at line {}.", ch, line).as_str(),
-                            );
-                        }
-                        UnterminatedString { line } => {
-                            self.error(
-                                line,
-                                format!("Unterminated string at line {}.", line).as_str(),
-                            );
-                        }
+                for statement in statements.unwrap_or_default() {
+                    println!("{}", AstPrinter {}.print_stmt(&statement));
+                }
+            }
+            Command::Env => {
+                for (name, value) in self.interpreter.globals() {
+                    println!("{} = {}", name, value);
+                }
+            }
+            Command::Load(file_name) => match fs::read_to_string(&file_name) {
+                Ok(source) => {
+                    let file = self.source_map.add(&file_name, source);
+                    self.run(file, false);
+                }
+                Err(err) => eprintln!("Cannot open {}: {}", file_name, err),
+            },
+            Command::Reset => {
+                let script_args = self.interpreter.script_args().to_vec();
+                self.interpreter = Interpreter::new(script_args);
+                println!("Session reset.");
+            }
+            Command::Time(source) => {
+                let file = self.source_map.add(&format!("<repl:{}>", entry), source);
+                *entry += 1;
+                let start = Instant::now();
+                self.run(file, true);
+                println!("Elapsed: {:?}", start.elapsed());
+            }
+            Command::Help => println!("{}", repl::HELP),
+        }
+
+        self.had_error = false;
+        self.had_runtime_error = false;
+    }
+
+    /// Apply every machine-applicable fix to a script. The parser only recovers
+    /// at the next statement, so an error can hide others after it in the same
+    /// statement, and a fix can change how the rest parses. This re-checks the
+    /// source after each round of edits until nothing more can be fixed. With
+    /// `dry_run` the file is left alone and a diff is printed instead.
+    fn fix_file(&mut self, file_name: &str, dry_run: bool) -> Result<(), io::Error> {
+        let original = fs::read_to_string(file_name)?;
+        let mut fixed = original.clone();
+        let mut fix_count = 0;
+        for _ in 0..MAX_FIX_ROUNDS {
+            let (_, diagnostics) = Self::analyze(&fixed, false);
+            let fixes: Vec<Fix> = diagnostics
+                .into_iter()
+                .filter_map(|diagnostic| diagnostic.fix)
+                .filter(|fix| fix.machine_applicable)
+                .collect();
+            if fixes.is_empty() {
+                break;
+            }
+
+            let edits: Vec<Edit> = fixes.iter().flat_map(|fix| fix.edits.clone()).collect();
+            fixed = apply_edits(&fixed, &edits);
+            fix_count += fixes.len();
+        }
+
+        if dry_run {
+            if fixed != original {
+                print!("{}", diagnostic::diff(file_name, &original, &fixed));
+            }
+        } else if fixed != original {
+            fs::write(file_name, &fixed)?;
+            println!("Applied {} fix(es) to {}.", fix_count, file_name);
+        }
+
+        // Whatever is left needs a human
+        let file = self.source_map.add(file_name, fixed);
+        let (_, diagnostics) = Self::analyze(self.source_map.source(file), false);
+        for diagnostic in diagnostics {
+            self.emit(file, diagnostic);
+        }
+        if self.had_error {
+            process::exit(EX_DATAERR);
+        }
+        Ok(())
+    }
+
+    /// Scan, parse and lint a source, collecting diagnostics rather than
+    /// reporting them. `repl` lets the last statement leave off its ';'.
+    fn analyze(source: &str, repl: bool) -> (Option<Vec<Stmt>>, Vec<Diagnostic>) {
+        let mut tokens = Vec::new();
+        for token_res in scanner::Scanner::new(source) {
+            match token_res {
+                Ok(token) => {
+                    let is_eof = token.token_type == TokenType::Eof;
+                    tokens.push(token);
+                    if is_eof {
+                        break;
                     }
-                    break;
                 }
+                Err(err) => return (None, vec![err.into()]),
+            }
+        }
+
+        let mut parser = if repl {
+            parser::Parser::for_repl(tokens)
+        } else {
+            parser::Parser::new(tokens)
+        };
+        let statements = match parser.parse() {
+            Ok(statements) => statements,
+            Err(errors) => {
+                let diagnostics = errors
+                    .into_iter()
+                    .map(|err| Diagnostic::from_parse_error(err, source))
+                    .collect();
+                return (None, diagnostics);
             }
+        };
+
+        let warnings = Linter::default().check(&Ast::lower(&statements));
+        (
+            Some(statements),
+            warnings.into_iter().map(Diagnostic::from).collect(),
+        )
+    }
+
+    /// Run a script, or one REPL entry when `repl` is set. An entry that is a
+    /// lone expression statement has its value printed.
+    fn run(&mut self, file: FileId, repl: bool) {
+        let (statements, diagnostics) = Self::analyze(self.source_map.source(file), repl);
+        for diagnostic in diagnostics {
+            self.emit(file, diagnostic);
+        }
+
+        let Some(statements) = statements else {
+            return;
+        };
+        if self.had_error {
+            return;
         }
+        self.execute(file, statements, repl);
     }
 
-    fn error(&mut self, line: usize, message: &str) {
-        self.report(line, "", message);
+    /// Desugar and interpret statements parsed from `file`, reporting any
+    /// runtime error.
+    fn execute(&mut self, file: FileId, statements: Vec<Stmt>, repl: bool) {
+        let statements = desugar(statements);
+        let result = match &statements[..] {
+            [Stmt::Expression { expression }] if repl => self
+                .interpreter
+                .evaluate(expression)
+                .map(|value| println!("{}", value)),
+            _ => self.interpreter.interpret(&statements),
+        };
+        let frames = self.interpreter.take_frames();
+        match result {
+            Ok(()) => {}
+            Err(RuntimeError::Exit { code }) => self.exit_code = Some(code),
+            Err(err) => {
+                self.emit(file, err.into());
+                eprint!(
+                    "{}",
+                    interpreter::backtrace(&frames, |call_site| {
+                        let location = self.source_map.location(file, call_site.offset);
+                        format!("{}:{}", self.source_map.name(file), location.line)
+                    })
+                );
+                self.had_runtime_error = true;
+            }
+        }
     }
 
-    fn report(&mut self, line: usize, loc: &str, message: &str) {
-        let mut err_out = io::stderr();
-        let msg = format!("[line {line}] Error {loc}: {message}");
+    /// Report a diagnostic on stderr. Lints are reported at the level they're
+    /// configured for: not at all for `Allow`, as a warning for `Warn`, and as
+    /// an error (failing the run) for `Deny`.
+    fn emit(&mut self, file: FileId, diagnostic: Diagnostic) {
+        let Diagnostic {
+            severity,
+            offset,
+            context,
+            mut message,
+            lint,
+            fix,
+        } = diagnostic;
 
+        let severity = match lint.map(|lint| (lint, self.lint_levels.level(lint))) {
+            Some((_, LintLevel::Allow)) => return,
+            Some((lint, LintLevel::Warn)) => {
+                message.push_str(&format!(" [-W {lint}]"));
+                Severity::Warning
+            }
+            Some((lint, LintLevel::Deny)) => {
+                message.push_str(&format!(" [-D {lint}]"));
+                Severity::Error
+            }
+            None => severity,
+        };
+
+        let position = self.position(file, offset);
+        let mut msg = match severity {
+            Severity::Error => format!("[{position}] Error{context}: {message}\n"),
+            Severity::Warning => format!("[{position}] Warning{context}: {message}\n"),
+        };
+        if let Some(fix) = fix {
+            msg.push_str(&format!("    help: {}\n", fix.message));
+        }
+
+        let mut err_out = io::stderr();
         let Ok(_) = err_out.write_all(msg.as_bytes()) else {
             panic!("Failed to write error to stderr");
         };
-        self.had_error = true;
+        if severity == Severity::Error {
+            self.had_error = true;
+        }
+    }
+
+    /// `file:line:column` for a byte offset, as shown in front of diagnostics.
+    fn position(&self, file: FileId, offset: usize) -> String {
+        let location = self.source_map.location(file, offset);
+        format!(
+            "{}:{}:{}",
+            self.source_map.name(file),
+            location.line,
+            location.column
+        )
     }
 }
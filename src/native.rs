@@ -0,0 +1,160 @@
+use std::{env, fmt};
+
+use crate::{
+    interpreter::{Interpreter, RuntimeError},
+    token::Token,
+    value::Value,
+};
+
+/// The Rust side of a native function. `paren` is the call's closing ')',
+/// for errors to point at.
+pub type NativeFn = fn(&Interpreter, &Token, &[Value]) -> Result<Value, RuntimeError>;
+
+/// A function built into the interpreter rather than written in Lox.
+#[derive(Clone, Copy)]
+pub struct NativeFunction {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: NativeFn,
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+/// Every native function, defined as a global in each new interpreter.
+pub const NATIVES: [NativeFunction; 4] = [
+    NativeFunction {
+        name: "args",
+        arity: 0,
+        function: args,
+    },
+    NativeFunction {
+        name: "arg",
+        arity: 1,
+        function: arg,
+    },
+    NativeFunction {
+        name: "getenv",
+        arity: 1,
+        function: getenv,
+    },
+    NativeFunction {
+        name: "exit",
+        arity: 1,
+        function: exit,
+    },
+];
+
+/// `args()`: how many arguments the script was given. Lox has no lists, so
+/// they're fetched one at a time with `arg`.
+fn args(interpreter: &Interpreter, _paren: &Token, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Number(interpreter.script_args().len() as f64))
+}
+
+/// `arg(i)`: the script's `i`th argument, counting from 0, or `nil` past the end.
+fn arg(
+    interpreter: &Interpreter,
+    paren: &Token,
+    arguments: &[Value],
+) -> Result<Value, RuntimeError> {
+    let index = integer_argument(paren, &arguments[0])?;
+    let value = usize::try_from(index)
+        .ok()
+        .and_then(|index| interpreter.script_args().get(index))
+        .map_or(Value::Nil, |arg| Value::Str(arg.clone()));
+    Ok(value)
+}
+
+/// `getenv(name)`: an environment variable, or `nil` if it isn't set.
+fn getenv(_: &Interpreter, paren: &Token, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let Value::Str(name) = &arguments[0] else {
+        return Err(invalid_argument(paren, "Variable name must be a string."));
+    };
+    Ok(env::var(name).map_or(Value::Nil, Value::Str))
+}
+
+/// `exit(code)`: stop the program with the given exit code.
+fn exit(_: &Interpreter, paren: &Token, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let code = integer_argument(paren, &arguments[0])?;
+    let code =
+        i32::try_from(code).map_err(|_| invalid_argument(paren, "Exit code is out of range."))?;
+    Err(RuntimeError::Exit { code })
+}
+
+fn integer_argument(paren: &Token, value: &Value) -> Result<i64, RuntimeError> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => Ok(*n as i64),
+        _ => Err(invalid_argument(paren, "Argument must be an integer.")),
+    }
+}
+
+fn invalid_argument(paren: &Token, message: &str) -> RuntimeError {
+    RuntimeError::InvalidArgument {
+        paren: paren.clone(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::TokenType;
+
+    fn paren() -> Token {
+        Token::new(TokenType::RightParen, ")".to_string(), None, 0)
+    }
+
+    fn call(function: NativeFn, argument: Value) -> Result<Value, RuntimeError> {
+        let interpreter = Interpreter::new(vec!["first".to_string(), "second".to_string()]);
+        function(&interpreter, &paren(), &[argument])
+    }
+
+    #[test]
+    fn arg_is_nil_outside_the_arguments() {
+        assert_eq!(
+            call(arg, Value::Number(1.0)).unwrap(),
+            Value::Str("second".to_string())
+        );
+        assert_eq!(call(arg, Value::Number(2.0)).unwrap(), Value::Nil);
+        assert_eq!(call(arg, Value::Number(-1.0)).unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn indexes_and_codes_must_be_integers() {
+        for function in [arg as NativeFn, exit] {
+            for argument in [Value::Number(1.5), Value::Str("1".to_string())] {
+                assert!(matches!(
+                    call(function, argument),
+                    Err(RuntimeError::InvalidArgument { message, .. })
+                        if message == "Argument must be an integer."
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn exit_codes_must_fit_an_i32() {
+        assert!(matches!(
+            call(exit, Value::Number(3.0)),
+            Err(RuntimeError::Exit { code: 3 })
+        ));
+        assert!(matches!(
+            call(exit, Value::Number(-1.0)),
+            Err(RuntimeError::Exit { code: -1 })
+        ));
+        assert!(matches!(
+            call(exit, Value::Number(2f64.powi(31))),
+            Err(RuntimeError::InvalidArgument { message, .. })
+                if message == "Exit code is out of range."
+        ));
+    }
+}
@@ -1,29 +1,212 @@
 use crate::{
-    ast::expr::Expr,
+    ast::{expr::Expr, stmt::Stmt},
+    suggest,
     token::{Token, TokenType},
 };
 
+#[derive(Debug)]
+pub enum ParseError {
+    ExpectedExpression {
+        token: Token,
+    },
+    UnclosedGrouping {
+        token: Token,
+        /// Byte offset just past the grouped expression, where the ')' belongs.
+        expected_at: usize,
+    },
+    MissingSemicolon {
+        token: Token,
+        /// Byte offset just past the statement, where the ';' belongs.
+        expected_at: usize,
+        /// What the ';' should follow, e.g. "value" or "variable declaration".
+        after: &'static str,
+    },
+    /// An identifier where a statement should start that looks like a
+    /// misspelled keyword, such as `retrun`.
+    MisspelledKeyword {
+        token: Token,
+        keyword: &'static str,
+    },
+    UnclosedCall {
+        token: Token,
+        /// Byte offset just past the last argument, where the ')' belongs.
+        expected_at: usize,
+    },
+    ExpectedVariableName {
+        token: Token,
+    },
+    InvalidAssignmentTarget {
+        token: Token,
+    },
+}
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<ParseError>,
+    /// In the REPL a final expression statement may leave off its ';'.
+    repl: bool,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+            repl: false,
+        }
+    }
+
+    pub fn for_repl(tokens: Vec<Token>) -> Self {
+        Parser {
+            repl: true,
+            ..Parser::new(tokens)
+        }
+    }
+
+    /// Program Grammar:
+    /// program -> declaration* EOF ;
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        let mut statements = Vec::new();
+        while !self.is_at_end() {
+            match self.declaration() {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.synchronize();
+                }
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Declaration Grammar:
+    /// declaration -> varDecl | statement ;
+    fn declaration(&mut self) -> Result<Stmt, ParseError> {
+        if self.match_terminals(&[TokenType::Var]) {
+            return self.var_declaration();
+        }
+
+        self.statement()
+    }
+
+    /// Variable Declaration Grammar:
+    /// varDecl -> "var" IDENTIFIER ( "=" expression )? ";" ;
+    fn var_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().unwrap();
+        let name = self.consume(TokenType::Identifier, |token| {
+            ParseError::ExpectedVariableName { token }
+        })?;
+
+        let initializer = if self.match_terminals(&[TokenType::Equal]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        self.consume_semicolon("variable declaration")?;
+        Ok(Stmt::Var {
+            keyword,
+            name,
+            initializer,
+        })
+    }
+
+    /// Statement Grammar:
+    /// statement -> exprStmt | printStmt ;
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        if self.match_terminals(&[TokenType::Print]) {
+            return self.print_statement();
+        }
+
+        self.expression_statement()
+    }
+
+    /// Print Statement Grammar:
+    /// printStmt -> "print" expression ";" ;
+    fn print_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().unwrap();
+        let expression = self.expression()?;
+        self.consume_semicolon("value")?;
+        Ok(Stmt::Print {
+            keyword,
+            expression,
+        })
+    }
+
+    /// Expression Statement Grammar:
+    /// exprStmt -> expression ";" ;
+    fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
+        let expression = self.expression()?;
+
+        // `retrun x;` parses as the variable `retrun` followed by another
+        // expression, and `whiel (x) print x;` as a call to `whiel` followed
+        // by another statement. A bare `classes` or `printer` is just a
+        // variable, and a bare `iff(x)` just a call.
+        let misspelled = match &expression {
+            Expr::Variable { name } if self.starts_expression() => Some(name),
+            Expr::Call { callee, .. } if self.starts_statement() => match callee.as_ref() {
+                Expr::Variable { name } => Some(name),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(name) = misspelled {
+            if let Some(keyword) = suggest::keyword(&name.lexeme) {
+                return Err(ParseError::MisspelledKeyword {
+                    token: name.clone(),
+                    keyword,
+                });
+            }
+        }
+
+        if !(self.repl && self.is_at_end()) {
+            self.consume_semicolon("expression")?;
+        }
+        Ok(Stmt::Expression { expression })
     }
 
-    fn expression(&mut self) -> Expr {
-        self.equality()
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        self.assignment()
+    }
+
+    /// Assignment Grammar:
+    /// assignment -> IDENTIFIER "=" assignment | equality ;
+    fn assignment(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.equality()?;
+
+        if self.match_terminals(&[TokenType::Equal]) {
+            let equals = self.previous().unwrap();
+            let value = self.assignment()?;
+
+            if let Expr::Variable { name } = expr {
+                return Ok(Expr::Assign {
+                    name,
+                    value: Box::new(value),
+                });
+            }
+
+            // Report it, but there's no need to synchronize: the parser isn't confused
+            self.errors
+                .push(ParseError::InvalidAssignmentTarget { token: equals });
+        }
+
+        Ok(expr)
     }
 
     /// Equality Grammar:
     /// equality -> comparison ( ( "!=" | "==" ) comparison )* ;
-    fn equality(&mut self) -> Expr {
-        let mut expr = self.comparison();
+    fn equality(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.comparison()?;
         while self.match_terminals(&[TokenType::BangEqual, TokenType::EqualEqual]) {
             let operator = self.previous();
-            let right = self.comparison();
+            let right = self.comparison()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 right: Box::new(right),
@@ -31,11 +214,241 @@ impl Parser {
             }
         }
 
-        return expr;
+        Ok(expr)
+    }
+
+    /// Comparison Grammar:
+    /// comparison -> term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term()?;
+        while self.match_terminals(&[
+            TokenType::Greater,
+            TokenType::GreaterEqual,
+            TokenType::Less,
+            TokenType::LessEqual,
+        ]) {
+            let operator = self.previous();
+            let right = self.term()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                right: Box::new(right),
+                operator: operator.unwrap(),
+            }
+        }
+
+        Ok(expr)
     }
 
-    fn comparison(&self) -> Expr {
-        todo!()
+    /// Term Grammar:
+    /// term -> factor ( ( "-" | "+" ) factor )* ;
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.factor()?;
+        while self.match_terminals(&[TokenType::Minus, TokenType::Plus]) {
+            let operator = self.previous();
+            let right = self.factor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                right: Box::new(right),
+                operator: operator.unwrap(),
+            }
+        }
+
+        Ok(expr)
+    }
+
+    /// Factor Grammar:
+    /// factor -> unary ( ( "/" | "*" ) unary )* ;
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        while self.match_terminals(&[TokenType::Slash, TokenType::Star]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                right: Box::new(right),
+                operator: operator.unwrap(),
+            }
+        }
+
+        Ok(expr)
+    }
+
+    /// Unary Grammar:
+    /// unary -> ( "!" | "-" ) unary | call ;
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.match_terminals(&[TokenType::Bang, TokenType::Minus]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            return Ok(Expr::Unary {
+                operator: operator.unwrap(),
+                right: Box::new(right),
+            });
+        }
+
+        self.call()
+    }
+
+    /// Call Grammar:
+    /// call -> primary ( "(" arguments? ")" )* ;
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+        while self.match_terminals(&[TokenType::LeftParen]) {
+            expr = self.finish_call(expr)?;
+        }
+
+        Ok(expr)
+    }
+
+    /// Arguments Grammar:
+    /// arguments -> expression ( "," expression )* ;
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let mut arguments = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            loop {
+                arguments.push(self.expression()?);
+                if !self.match_terminals(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let expected_at = self.previous().map_or(0, |t| t.offset + t.lexeme.len());
+        let paren = self.consume(TokenType::RightParen, |token| ParseError::UnclosedCall {
+            token,
+            expected_at,
+        })?;
+
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        })
+    }
+
+    /// Primary Grammar:
+    /// primary -> NUMBER | STRING | "true" | "false" | "nil" | IDENTIFIER | "(" expression ")" ;
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        if self.match_terminals(&[
+            TokenType::False,
+            TokenType::True,
+            TokenType::Nil,
+            TokenType::Number,
+            TokenType::String,
+        ]) {
+            let mut token = self.previous().unwrap();
+            return Ok(Expr::Literal {
+                value: token
+                    .literal
+                    .take()
+                    .expect("the scanner gives literal tokens their value"),
+                token,
+            });
+        }
+
+        if self.match_terminals(&[TokenType::Identifier]) {
+            return Ok(Expr::Variable {
+                name: self.previous().unwrap(),
+            });
+        }
+
+        if self.match_terminals(&[TokenType::LeftParen]) {
+            let left_paren = self.previous().unwrap();
+            let expression = self.expression()?;
+            let expected_at = self.previous().map_or(0, |t| t.offset + t.lexeme.len());
+            let right_paren = self.consume(TokenType::RightParen, |token| {
+                ParseError::UnclosedGrouping { token, expected_at }
+            })?;
+            return Ok(Expr::Grouping {
+                left_paren,
+                expression: Box::new(expression),
+                right_paren,
+            });
+        }
+
+        Err(ParseError::ExpectedExpression {
+            token: self.peek_or_last(),
+        })
+    }
+
+    /// Consume the next token if it has the expected type, otherwise build an error
+    /// pointing at the offending token.
+    fn consume(
+        &mut self,
+        token_type: TokenType,
+        err: impl FnOnce(Token) -> ParseError,
+    ) -> Result<Token, ParseError> {
+        if self.check(token_type) {
+            return Ok(self.advance().unwrap());
+        }
+
+        Err(err(self.peek_or_last()))
+    }
+
+    /// Whether the next token could begin an expression.
+    fn starts_expression(&self) -> bool {
+        [
+            TokenType::Identifier,
+            TokenType::Number,
+            TokenType::String,
+            TokenType::True,
+            TokenType::False,
+            TokenType::Nil,
+            TokenType::LeftParen,
+            TokenType::Minus,
+            TokenType::Bang,
+        ]
+        .into_iter()
+        .any(|token_type| self.check(token_type))
+    }
+
+    fn starts_statement(&self) -> bool {
+        self.starts_expression()
+            || [
+                TokenType::Print,
+                TokenType::Var,
+                TokenType::LeftBrace,
+                TokenType::If,
+                TokenType::While,
+                TokenType::For,
+                TokenType::Fun,
+                TokenType::Class,
+                TokenType::Return,
+            ]
+            .into_iter()
+            .any(|token_type| self.check(token_type))
+    }
+
+    fn consume_semicolon(&mut self, after: &'static str) -> Result<Token, ParseError> {
+        let expected_at = self.previous().map_or(0, |t| t.offset + t.lexeme.len());
+        self.consume(TokenType::Semicolon, |token| ParseError::MissingSemicolon {
+            token,
+            expected_at,
+            after,
+        })
+    }
+
+    /// Discard tokens until we're probably at a statement boundary, so one
+    /// syntax error doesn't cascade into a pile of bogus ones.
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.is_at_end() {
+            if self
+                .previous()
+                .is_some_and(|t| t.token_type == TokenType::Semicolon)
+            {
+                return;
+            }
+
+            use TokenType::*;
+            if matches!(
+                self.peek().map(|t| t.token_type),
+                Some(Class | Fun | Var | For | If | While | Print | Return)
+            ) {
+                return;
+            }
+
+            self.advance();
+        }
     }
 
     /// Match terminals in grammar
@@ -46,7 +459,7 @@ impl Parser {
                 return true;
             }
         }
-        return false;
+        false
     }
 
     fn check(&self, token_type: TokenType) -> bool {
@@ -55,18 +468,18 @@ impl Parser {
         }
 
         self.peek()
-            .map_or(false, |t: Token| t.token_type == token_type)
+            .is_some_and(|t: Token| t.token_type == token_type)
     }
 
     fn previous(&self) -> Option<Token> {
         self.tokens.get(self.current - 1).cloned()
     }
 
-    fn advance(&mut self) -> Option<Token>{
+    fn advance(&mut self) -> Option<Token> {
         if !self.is_at_end() {
             self.current += 1
         }
-        return self.previous()
+        self.previous()
     }
 
     fn is_at_end(&self) -> bool {
@@ -79,4 +492,79 @@ impl Parser {
     fn peek(&self) -> Option<Token> {
         self.tokens.get(self.current).cloned()
     }
+
+    /// The current token, falling back to the last one when the stream has run dry.
+    fn peek_or_last(&self) -> Token {
+        self.peek()
+            .or_else(|| self.tokens.last().cloned())
+            .unwrap_or_else(|| Token::new(TokenType::Eof, "".to_string(), None, 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        for token in Scanner::new(source) {
+            let token = token.expect("source should scan");
+            let is_eof = token.token_type == TokenType::Eof;
+            tokens.push(token);
+            if is_eof {
+                return tokens;
+            }
+        }
+        unreachable!("the scanner always ends with Eof")
+    }
+
+    fn suggested_keyword(source: &str) -> Option<&'static str> {
+        let errors = Parser::new(tokens(source)).parse().unwrap_err();
+        match errors.first() {
+            Some(ParseError::MisspelledKeyword { keyword, .. }) => Some(keyword),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn misspelled_keywords_are_caught_before_an_expression() {
+        assert_eq!(suggested_keyword("retrun x;"), Some("return"));
+        assert_eq!(suggested_keyword("fucntion f() {}"), Some("fun"));
+    }
+
+    #[test]
+    fn misspelled_keywords_are_caught_when_they_look_like_calls() {
+        assert_eq!(suggested_keyword("whiel (x) print x;"), Some("while"));
+        assert_eq!(suggested_keyword("iff (c) { print c; }"), Some("if"));
+
+        // A call the statement ends after is a call missing its ';'
+        assert_eq!(suggested_keyword("iff(c)"), None);
+        assert_eq!(suggested_keyword("draw (x) print x;"), None);
+    }
+
+    #[test]
+    fn variables_named_like_keywords_echo_in_the_repl() {
+        for source in ["classes", "printer"] {
+            let statements = Parser::for_repl(tokens(source)).parse().unwrap();
+            assert!(matches!(
+                &statements[..],
+                [Stmt::Expression {
+                    expression: Expr::Variable { name },
+                }] if name.lexeme == source
+            ));
+        }
+
+        // Outside the REPL the ';' is what's missing
+        let errors = Parser::new(tokens("printer")).parse().unwrap_err();
+        assert!(matches!(errors[..], [ParseError::MissingSemicolon { .. }]));
+    }
+
+    #[test]
+    fn only_expressions_leave_off_their_semicolon_in_the_repl() {
+        for source in ["var a = 1", "print a"] {
+            let errors = Parser::for_repl(tokens(source)).parse().unwrap_err();
+            assert!(matches!(errors[..], [ParseError::MissingSemicolon { .. }]));
+        }
+    }
 }
@@ -0,0 +1,366 @@
+use std::{borrow::Cow, env, path::PathBuf};
+
+use rustyline::{
+    completion::Completer,
+    highlight::{CmdKind, Highlighter},
+    hint::Hinter,
+    validate::Validator,
+    Context, Helper,
+};
+
+use crate::{
+    scanner::{ScanError, Scanner, KEYWORDS},
+    token::TokenType,
+};
+
+/// Keywords that start a statement other than an expression statement.
+const STATEMENT_KEYWORDS: [TokenType; 8] = [
+    TokenType::Class,
+    TokenType::For,
+    TokenType::Fun,
+    TokenType::If,
+    TokenType::Print,
+    TokenType::Return,
+    TokenType::Var,
+    TokenType::While,
+];
+
+/// Whether the REPL should keep reading before running `source`: a string or
+/// block comment is still open, there are more `(`/`{` than closers, or the
+/// last statement starts with a keyword and hasn't reached its `;`. A lone
+/// expression may leave off its `;`, so it runs once its brackets balance.
+/// Anything that's simply wrong is treated as complete so the error gets
+/// reported rather than waiting for input that won't fix it.
+pub fn is_incomplete(source: &str) -> bool {
+    let mut depth: i32 = 0;
+    // The first token of the top-level statement being read, if any
+    let mut statement_start = None;
+    for token_res in Scanner::new(source) {
+        match token_res {
+            Ok(token) => {
+                match token.token_type {
+                    TokenType::Eof => break,
+                    TokenType::LeftParen | TokenType::LeftBrace => depth += 1,
+                    TokenType::RightParen | TokenType::RightBrace => {
+                        depth -= 1;
+                        if depth < 0 {
+                            return false;
+                        }
+                    }
+                    _ => {}
+                }
+                if depth == 0
+                    && matches!(
+                        token.token_type,
+                        TokenType::Semicolon | TokenType::RightBrace
+                    )
+                {
+                    statement_start = None;
+                } else if statement_start.is_none() {
+                    statement_start = Some(token.token_type);
+                }
+            }
+            Err(ScanError::UnterminatedString { .. } | ScanError::UnterminatedComment { .. }) => {
+                return true
+            }
+            Err(ScanError::UnexpectedCharacter { .. }) => return false,
+        }
+    }
+
+    depth > 0 || statement_start.is_some_and(|start| STATEMENT_KEYWORDS.contains(&start))
+}
+
+pub const HELP: &str = "\
+Commands:
+  :tokens <source>  show the tokens the scanner produces
+  :ast <source>     show the syntax tree the parser produces
+  :env              list global variables and their values
+  :load <file>      run a script in this session
+  :reset            forget every global
+  :time <source>    run source and report how long it took
+  :help             show this message
+Anything else is run as Lox. A lone expression has its value printed.";
+
+/// A REPL meta-command: a line starting with ':'.
+pub enum Command {
+    Tokens(String),
+    Ast(String),
+    Env,
+    Load(String),
+    Reset,
+    Time(String),
+    Help,
+}
+
+impl Command {
+    /// Parse a line starting with ':' into a command, or explain what's wrong
+    /// with it.
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let line = line.trim().trim_start_matches(':');
+        let (name, arg) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let arg = arg.trim().to_string();
+
+        let needs_arg = |what: &str| {
+            if arg.is_empty() {
+                Err(format!("Usage: :{} <{}>", name, what))
+            } else {
+                Ok(arg.clone())
+            }
+        };
+        match name {
+            "tokens" => needs_arg("source").map(Command::Tokens),
+            "ast" => needs_arg("source").map(Command::Ast),
+            "env" => Ok(Command::Env),
+            "load" => needs_arg("file").map(Command::Load),
+            "reset" => Ok(Command::Reset),
+            "time" => needs_arg("source").map(Command::Time),
+            "help" => Ok(Command::Help),
+            _ => Err(format!("Unknown command ':{}'. Try :help.", name)),
+        }
+    }
+}
+
+const COMMAND_NAMES: [&str; 7] = ["tokens", "ast", "env", "load", "reset", "time", "help"];
+
+/// Plugs the REPL into rustyline: colours the line as it's typed and completes
+/// names on Tab.
+#[derive(Default)]
+pub struct ReplHelper {
+    /// Names defined in the session, refreshed after each entry runs.
+    globals: Vec<String>,
+}
+
+impl ReplHelper {
+    pub fn set_globals(&mut self, globals: Vec<String>) {
+        self.globals = globals;
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        Cow::Owned(highlight(line))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _kind: CmdKind) -> bool {
+        // Any keystroke can change how the rest of the line scans
+        true
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    /// Complete the word before the cursor: a command name after a leading
+    /// ':', otherwise a keyword or global. There are no instances yet, so
+    /// nothing is offered after a '.'.
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .char_indices()
+            .rfind(|&(_, c)| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let prefix = &line[start..pos];
+
+        let words: Vec<&str> = match &line[..start] {
+            ":" => COMMAND_NAMES.to_vec(),
+            before if before.ends_with('.') => Vec::new(),
+            _ => KEYWORDS
+                .iter()
+                .map(|(keyword, _)| *keyword)
+                .chain(self.globals.iter().map(String::as_str))
+                .collect(),
+        };
+
+        let mut candidates: Vec<String> = words
+            .into_iter()
+            .filter(|word| word.starts_with(prefix))
+            .map(String::from)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Where REPL history is kept between sessions: `~/.rulox_history`.
+pub fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".rulox_history"))
+}
+
+const RESET: &str = "\x1b[0m";
+const KEYWORD: &str = "\x1b[1;35m";
+const CONSTANT: &str = "\x1b[36m";
+const NUMBER: &str = "\x1b[33m";
+const STRING: &str = "\x1b[32m";
+const COMMENT: &str = "\x1b[90m";
+const INVALID: &str = "\x1b[31m";
+
+/// Wrap each token of `line` in an ANSI colour picked from its `TokenType`.
+/// Comments are whatever non-blank text the scanner skipped between tokens.
+pub fn highlight(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() * 2);
+    // Everything before this offset has already been written out
+    let mut written = 0;
+
+    for token_res in Scanner::new(line) {
+        match token_res {
+            Ok(token) => {
+                use TokenType::*;
+                let colour = match token.token_type {
+                    Eof => break,
+                    True | False | Nil => Some(CONSTANT),
+                    And | Class | Else | Fun | For | If | Or | Print | Return | Super | This
+                    | Var | While => Some(KEYWORD),
+                    Number => Some(NUMBER),
+                    String => Some(STRING),
+                    _ => None,
+                };
+                let end = token.offset + token.lexeme.len();
+                paint(&mut out, line, &mut written, token.offset, end, colour);
+            }
+            Err(ScanError::UnexpectedCharacter { offset, ch }) => {
+                paint(
+                    &mut out,
+                    line,
+                    &mut written,
+                    offset,
+                    offset + ch.len_utf8(),
+                    Some(INVALID),
+                );
+            }
+            // Still being typed: colour the rest of the line as what it will become
+            Err(ScanError::UnterminatedString { offset }) => {
+                paint(
+                    &mut out,
+                    line,
+                    &mut written,
+                    offset,
+                    line.len(),
+                    Some(STRING),
+                );
+                break;
+            }
+            Err(ScanError::UnterminatedComment { offset }) => {
+                paint(
+                    &mut out,
+                    line,
+                    &mut written,
+                    offset,
+                    line.len(),
+                    Some(COMMENT),
+                );
+                break;
+            }
+        }
+    }
+    paint(&mut out, line, &mut written, line.len(), line.len(), None);
+
+    out
+}
+
+/// Copy `line[start..end]` to `out` in `colour`, along with the skipped text
+/// before it (greyed out if it's a comment rather than whitespace).
+fn paint(
+    out: &mut String,
+    line: &str,
+    written: &mut usize,
+    start: usize,
+    end: usize,
+    colour: Option<&str>,
+) {
+    let gap = &line[*written..start];
+    if gap.trim().is_empty() {
+        out.push_str(gap);
+    } else {
+        out.push_str(&format!("{COMMENT}{gap}{RESET}"));
+    }
+    match colour {
+        Some(colour) => out.push_str(&format!("{colour}{}{RESET}", &line[start..end])),
+        None => out.push_str(&line[start..end]),
+    }
+    *written = end;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::Interpreter;
+    use rustyline::history::MemHistory;
+
+    /// Where completion starts in `line`, with the cursor at its end, and
+    /// what it offers.
+    fn complete(helper: &ReplHelper, line: &str) -> (usize, Vec<String>) {
+        let history = MemHistory::new();
+        helper
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap()
+    }
+
+    fn session_helper() -> ReplHelper {
+        let mut helper = ReplHelper::default();
+        let globals = Interpreter::new(Vec::new()).globals();
+        helper.set_globals(globals.into_iter().map(|(name, _)| name).collect());
+        helper
+    }
+
+    #[test]
+    fn completes_keywords_and_natives() {
+        let helper = session_helper();
+        assert_eq!(
+            complete(&helper, "print ar"),
+            (6, vec!["arg".to_string(), "args".to_string()])
+        );
+        assert_eq!(complete(&helper, "va"), (0, vec!["var".to_string()]));
+        assert_eq!(complete(&helper, "x = ge"), (4, vec!["getenv".to_string()]));
+    }
+
+    #[test]
+    fn completes_commands_after_a_colon_and_nothing_after_a_dot() {
+        let helper = session_helper();
+        assert_eq!(
+            complete(&helper, ":t"),
+            (1, vec!["time".to_string(), "tokens".to_string()])
+        );
+        assert_eq!(complete(&helper, "a.ar"), (2, Vec::new()));
+    }
+
+    #[test]
+    fn words_can_follow_non_ascii_text() {
+        let helper = session_helper();
+        assert_eq!(complete(&helper, "\"éa").0, 3);
+        assert_eq!(complete(&helper, "\"é\" + ar").1, ["arg", "args"]);
+    }
+
+    #[test]
+    fn open_brackets_strings_and_comments_are_incomplete() {
+        for source in ["{", "print (1 +", "\"abc", "/* note", "print f(\n  1,\n"] {
+            assert!(is_incomplete(source), "{:?}", source);
+        }
+        for source in ["{ }", "print (1);", "\"abc\"", "/* note */", "1 )("] {
+            assert!(!is_incomplete(source), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn statements_wait_for_their_semicolon() {
+        assert!(is_incomplete("var a = 1"));
+        assert!(is_incomplete("var a = 1;\nprint a"));
+        assert!(!is_incomplete("var a = 1;"));
+
+        // A lone expression is echoed without one
+        assert!(!is_incomplete("a + 1"));
+        assert!(!is_incomplete("var a = 1; a"));
+    }
+}
@@ -1,19 +1,39 @@
 use std::collections::HashMap;
 
-use crate::token::{Token, TokenLiteral, TokenType};
+use crate::token::{Literal, Token, TokenType};
 
 #[derive(Debug)]
 pub enum ScanError {
-    UnexpectedCharacter { line: usize, ch: char },
-    UnterminatedString { line: usize },
+    UnexpectedCharacter { offset: usize, ch: char },
+    UnterminatedString { offset: usize },
+    UnterminatedComment { offset: usize },
 }
 
+/// Reserved words and the token type each one scans to.
+pub const KEYWORDS: [(&str, TokenType); 16] = [
+    ("and", TokenType::And),
+    ("class", TokenType::Class),
+    ("else", TokenType::Else),
+    ("false", TokenType::False),
+    ("for", TokenType::For),
+    ("fun", TokenType::Fun),
+    ("if", TokenType::If),
+    ("nil", TokenType::Nil),
+    ("or", TokenType::Or),
+    ("print", TokenType::Print),
+    ("return", TokenType::Return),
+    ("super", TokenType::Super),
+    ("this", TokenType::This),
+    ("true", TokenType::True),
+    ("var", TokenType::Var),
+    ("while", TokenType::While),
+];
+
 pub struct Scanner<'a> {
     source: &'a str,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
-    line: usize,
     keywords: HashMap<String, TokenType>,
 }
 
@@ -24,46 +44,40 @@ impl<'a> Scanner<'a> {
             tokens: Vec::new(),
             start: 0,
             current: 0,
-            line: 1,
             keywords: HashMap::new(),
         };
         sc.init_keywords();
-        return sc;
+        sc.skip_shebang();
+        sc
+    }
+
+    /// Skip a `#!` line at the very start, so scripts can be made executable.
+    /// The newline is left in place, and offsets still count from the start
+    /// of the source, so positions after it come out right.
+    fn skip_shebang(&mut self) {
+        if self.source.starts_with("#!") {
+            self.current = self.source.find('\n').unwrap_or(self.source.len());
+            self.start = self.current;
+        }
     }
 
     fn init_keywords(&mut self) {
-        self.keywords.insert(String::from("and"), TokenType::And);
-        self.keywords
-            .insert(String::from("class"), TokenType::Class);
-        self.keywords.insert(String::from("else"), TokenType::Else);
-        self.keywords
-            .insert(String::from("false"), TokenType::False);
-        self.keywords.insert(String::from("for"), TokenType::For);
-        self.keywords.insert(String::from("fun"), TokenType::Fun);
-        self.keywords.insert(String::from("if"), TokenType::If);
-        self.keywords.insert(String::from("nil"), TokenType::Nil);
-        self.keywords.insert(String::from("or"), TokenType::Or);
-        self.keywords
-            .insert(String::from("print"), TokenType::Print);
-        self.keywords
-            .insert(String::from("return"), TokenType::Return);
-        self.keywords
-            .insert(String::from("super"), TokenType::Super);
-        self.keywords.insert(String::from("this"), TokenType::This);
-        self.keywords.insert(String::from("true"), TokenType::True);
-        self.keywords.insert(String::from("var"), TokenType::Var);
-        self.keywords
-            .insert(String::from("while"), TokenType::While);
+        for (keyword, token_type) in KEYWORDS {
+            self.keywords.insert(String::from(keyword), token_type);
+        }
     }
 
     fn is_at_end(&self) -> bool {
-        return self.current > self.source.len();
+        self.current > self.source.len()
     }
 
     fn advance(&mut self) -> Option<char> {
-        let char = self.source.chars().nth(self.current);
-        self.current += 1;
-        return char;
+        let char = self
+            .source
+            .get(self.current..)
+            .and_then(|s| s.chars().next());
+        self.current += char.map_or(1, |c| c.len_utf8());
+        char
     }
 
     fn peek(&self) -> char {
@@ -71,15 +85,15 @@ impl<'a> Scanner<'a> {
             return '\0';
         }
 
-        return self.source.chars().nth(self.current).unwrap_or('\0');
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
+        if self.is_at_end() {
             return '\0';
         }
 
-        return self.source.chars().nth(self.current + 1).unwrap_or('\0');
+        self.source[self.current..].chars().nth(1).unwrap_or('\0')
     }
 
     fn match_next(&mut self, expected: char) -> bool {
@@ -87,7 +101,7 @@ impl<'a> Scanner<'a> {
             return false;
         }
 
-        let Some(c) = self.source.chars().nth(self.current) else {
+        let Some(c) = self.source[self.current..].chars().next() else {
             return false;
         };
 
@@ -95,28 +109,24 @@ impl<'a> Scanner<'a> {
             return false;
         }
 
-        self.current += 1;
-        return true;
+        self.current += c.len_utf8();
+        true
     }
 
     fn string(&mut self) -> Result<String, ScanError> {
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
-            }
             self.advance();
         }
 
         if self.is_at_end() {
-            return Err(ScanError::UnterminatedString { line: self.line });
+            return Err(ScanError::UnterminatedString { offset: self.start });
         }
 
         // the closing ".
         self.advance();
 
         let value = &self.source[self.start + 1..self.current - 1];
-        println!("Parsed string {}", value);
-        return Ok(value.to_string());
+        Ok(value.to_string())
     }
 
     fn number(&mut self) -> f64 {
@@ -135,8 +145,7 @@ impl<'a> Scanner<'a> {
         }
 
         let value_str = &self.source[self.start..self.current];
-        let value_num = value_str.parse::<f64>().unwrap_or(0.0);
-        return value_num;
+        value_str.parse::<f64>().unwrap_or(0.0)
     }
 
     fn identifier(&mut self) -> Token {
@@ -146,7 +155,10 @@ impl<'a> Scanner<'a> {
 
         let text = &self.source[self.start..self.current];
         match self.keywords.get(text) {
-            Some(keyword) => self.add_token(keyword.clone()),
+            Some(TokenType::True) => self.add_token_(TokenType::True, Some(Literal::Bool(true))),
+            Some(TokenType::False) => self.add_token_(TokenType::False, Some(Literal::Bool(false))),
+            Some(TokenType::Nil) => self.add_token_(TokenType::Nil, Some(Literal::Nil)),
+            Some(keyword) => self.add_token(*keyword),
             None => self.add_token(TokenType::Identifier),
         }
     }
@@ -210,17 +222,15 @@ impl<'a> Scanner<'a> {
                     Ok(None)
                 } else if self.match_next('*') {
                     // multi line comment /* */
-                    while self.peek() != '*' && !self.is_at_end() {
+                    while !(self.peek() == '*' && self.peek_next() == '/') {
+                        if self.is_at_end() {
+                            return Err(ScanError::UnterminatedComment { offset: self.start });
+                        }
                         self.advance();
                     }
 
-                    // capture the closing "*"
+                    // capture the closing "*/"
                     self.advance();
-                    while self.peek() != '/' {
-                        self.advance();
-                    }
-
-                    // capture closing "/"
                     self.advance();
                     Ok(None)
                 } else {
@@ -231,31 +241,26 @@ impl<'a> Scanner<'a> {
                 let possible_str = self.string();
                 match possible_str {
                     Ok(str_token) => {
-                        let tok =
-                            self.add_token_(String, Some(TokenLiteral::from_string(&str_token)));
+                        let tok = self.add_token_(String, Some(Literal::Str(str_token)));
                         Ok(Some(tok))
                     }
                     Err(err) => Err(err),
                 }
             }
-            Some(' ' | '\r' | '\t') => Ok(None),
-            Some('\n') => {
-                self.line += 1;
-                Ok(None)
-            }
+            Some(' ' | '\r' | '\t' | '\n') => Ok(None),
             Some('\0') => Ok(None),
             None => Ok(None),
             Some(ch) => {
                 if ch.is_ascii_digit() {
                     let num = self.number();
-                    let tok = self.add_token_(Number, Some(TokenLiteral::from_number(num)));
+                    let tok = self.add_token_(Number, Some(Literal::Number(num)));
                     Ok(Some(tok))
                 } else if self.is_alpha(ch) {
                     let identifier = self.identifier();
                     Ok(Some(identifier))
                 } else {
                     Err(ScanError::UnexpectedCharacter {
-                        line: self.line,
+                        offset: self.start,
                         ch,
                     })
                 }
@@ -267,9 +272,9 @@ impl<'a> Scanner<'a> {
         self.add_token_(token_type, None)
     }
 
-    fn add_token_(&mut self, token_type: TokenType, literal: Option<TokenLiteral>) -> Token {
+    fn add_token_(&mut self, token_type: TokenType, literal: Option<Literal>) -> Token {
         let text = &self.source[self.start..self.current];
-        let new_token = Token::new(token_type, text.to_string(), literal, self.line);
+        let new_token = Token::new(token_type, text.to_string(), literal, self.start);
         // self.tokens.append(new_token.);
         new_token
     }
@@ -280,23 +285,85 @@ impl<'a> Iterator for Scanner<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.is_at_end() {
-            let new_token = Token::new(TokenType::Eof, "".to_string(), None, self.line);
+            let new_token = Token::new(TokenType::Eof, "".to_string(), None, self.source.len());
             return Some(Ok(new_token));
         }
 
         self.start = self.current;
 
         match self.scan_token() {
-            Ok(new_token) => {
-                if new_token.is_some() {
-                    let inner = new_token.unwrap();
-                    self.tokens.push(inner.clone());
-                    Some(Ok(inner))
-                } else {
-                    self.next()
-                }
+            Ok(Some(inner)) => {
+                self.tokens.push(inner.clone());
+                Some(Ok(inner))
             }
+            Ok(None) => self.next(),
             Err(err) => Some(Err(err)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_map::{Location, SourceMap};
+
+    /// Every token up to and including `Eof`, or the first error.
+    fn scan(source: &str) -> Vec<Result<Token, ScanError>> {
+        let mut results = Vec::new();
+        for result in Scanner::new(source) {
+            let done = match &result {
+                Ok(token) => token.token_type == TokenType::Eof,
+                Err(_) => true,
+            };
+            results.push(result);
+            if done {
+                return results;
+            }
+        }
+        unreachable!("the scanner always ends with Eof")
+    }
+
+    #[test]
+    fn a_shebang_line_is_skipped() {
+        let source = "#!/usr/bin/env rulox\nprint 1;";
+        let tokens = scan(source);
+        let Some(Ok(print)) = tokens.first() else {
+            panic!("expected a token, got {:?}", tokens);
+        };
+        assert_eq!(print.token_type, TokenType::Print);
+
+        let mut source_map = SourceMap::default();
+        let file = source_map.add("script.lox", source.to_string());
+        assert_eq!(
+            source_map.location(file, print.offset),
+            Location { line: 2, column: 1 }
+        );
+    }
+
+    #[test]
+    fn a_file_of_just_a_shebang_is_empty() {
+        for source in ["#!/usr/bin/env rulox", "#!/usr/bin/env rulox\n"] {
+            let tokens = scan(source);
+            assert!(
+                matches!(&tokens[..], [Ok(token)] if token.token_type == TokenType::Eof),
+                "{:?}",
+                tokens
+            );
+        }
+    }
+
+    #[test]
+    fn a_hash_anywhere_else_is_unexpected() {
+        for (source, at) in [("print 1; #!x", 9), (" #!/usr/bin/env rulox", 1)] {
+            let tokens = scan(source);
+            assert!(
+                matches!(
+                    tokens.last(),
+                    Some(Err(ScanError::UnexpectedCharacter { offset, ch: '#' })) if *offset == at
+                ),
+                "{:?}",
+                tokens
+            );
+        }
+    }
+}
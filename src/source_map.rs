@@ -0,0 +1,64 @@
+/// Identifies one source loaded into a `SourceMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(usize);
+
+/// A 1-based line and column, where the column counts characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+struct SourceFile {
+    name: String,
+    source: String,
+    /// Byte offset at which each line starts.
+    line_starts: Vec<usize>,
+}
+
+/// Owns every piece of source the interpreter has seen (scripts, REPL entries),
+/// so diagnostics can be reported against a file name, line and column from
+/// just a `FileId` and a byte offset.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn add(&mut self, name: &str, source: String) -> FileId {
+        let mut line_starts = vec![0];
+        line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+
+        self.files.push(SourceFile {
+            name: name.to_string(),
+            source,
+            line_starts,
+        });
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        &self.files[file.0].name
+    }
+
+    pub fn source(&self, file: FileId) -> &str {
+        &self.files[file.0].source
+    }
+
+    /// The name of every source, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|file| file.name.as_str())
+    }
+
+    /// Map a byte offset in `file` back to its line and column. Offsets past the
+    /// end resolve to the end of the file.
+    pub fn location(&self, file: FileId, offset: usize) -> Location {
+        let file = &self.files[file.0];
+        let offset = offset.min(file.source.len());
+        let line = file.line_starts.partition_point(|&start| start <= offset);
+        let line_start = file.line_starts[line - 1];
+        let column = file.source[line_start..offset].chars().count() + 1;
+
+        Location { line, column }
+    }
+}
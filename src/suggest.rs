@@ -0,0 +1,93 @@
+use crate::scanner::KEYWORDS;
+
+/// Words people reach for out of habit from other languages, and the Lox keyword
+/// they meant. Misspellings of these are matched too, so `fucntion` still
+/// suggests `fun`.
+const KEYWORD_ALIASES: [(&str, &str); 8] = [
+    ("function", "fun"),
+    ("func", "fun"),
+    ("def", "fun"),
+    ("let", "var"),
+    ("const", "var"),
+    ("null", "nil"),
+    ("self", "this"),
+    ("elif", "else"),
+];
+
+/// Edit distance between two words, counting insertions, deletions,
+/// substitutions and swaps of adjacent characters as one edit each.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // rows[i][j] is the distance between the first i chars of a and the first j of b
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+/// The candidate closest to `name`, provided it's near enough to plausibly be
+/// what was meant. Ties go to the candidate that sorts first, so the answer
+/// doesn't depend on the order candidates come in, such as a `HashMap`'s.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = name.chars().count() / 3;
+    let mut best: Option<(usize, &str)> = None;
+    for candidate in candidates {
+        if candidate == name {
+            continue;
+        }
+        let distance = edit_distance(name, candidate);
+        if distance <= max_distance && best.is_none_or(|best| (distance, candidate) < best) {
+            best = Some((distance, candidate));
+        }
+    }
+
+    best.map(|(_, candidate)| candidate)
+}
+
+/// The keyword an identifier was probably meant to be, if any.
+pub fn keyword(name: &str) -> Option<&'static str> {
+    if let Some((_, keyword)) = KEYWORD_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Some(keyword);
+    }
+
+    let words = KEYWORDS
+        .iter()
+        .map(|(keyword, _)| *keyword)
+        .chain(KEYWORD_ALIASES.iter().map(|(alias, _)| *alias));
+    let word = closest(name, words)?;
+
+    KEYWORD_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == word)
+        .map(|(_, keyword)| *keyword)
+        .or(Some(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ties_go_to_the_first_name_in_order() {
+        assert_eq!(closest("cart", ["care", "card"]), Some("card"));
+        assert_eq!(closest("cart", ["card", "care"]), Some("card"));
+    }
+}
@@ -1,7 +1,8 @@
-use std::fmt::{Display};
+use std::fmt::Display;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenType {
     // Single char tokens
     LeftParen,
@@ -53,88 +54,86 @@ pub enum TokenType {
     Eof,
 }
 
-#[derive(Debug, Clone)]
-pub struct TokenLiteral {
-    string_val: Option<String>,
-    numeric_val: Option<f64>,
-}
-
-impl TokenLiteral {
-    pub fn from_string(val: &str) -> Self {
-        TokenLiteral {
-            string_val: Some(val.to_owned()),
-            numeric_val: None,
-        }
-    }
-
-    pub fn from_number(val: f64) -> Self {
-        TokenLiteral {
-            string_val: None,
-            numeric_val: Some(val),
-        }
-    }
-
-    pub fn string_val(&self) -> Option<&String> {
-        self.string_val.as_ref()
-    }
-
-    pub fn num_val(&self) -> Option<f64> {
-        self.numeric_val
-    }
+/// The value a literal stands for, both on the token the scanner produces and
+/// in the `Expr::Literal` the parser builds from it. In JSON it's just the
+/// value, with `null` for nil.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum Literal {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    Nil,
 }
 
-impl Default for TokenLiteral {
-    fn default() -> Self {
-        Self {
-            string_val: Default::default(),
-            numeric_val: Default::default(),
+impl Literal {
+    /// The literal as it would be written in source, with strings escaped so
+    /// they stay on one line.
+    pub fn label(&self) -> String {
+        match self {
+            Literal::Str(val) => format!("{:?}", val),
+            _ => self.to_string(),
         }
     }
 }
 
-impl Display for TokenLiteral {
+impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.string_val.is_some() {
-            write!(f, "{}", self.string_val.as_ref().unwrap_or(&"".to_string()))
-        } else {
-            write!(f, "{}", self.numeric_val.unwrap_or_default())
+        match self {
+            Literal::Number(num) => write!(f, "{}", num),
+            Literal::Str(val) => write!(f, "{}", val),
+            Literal::Bool(val) => write!(f, "{}", val),
+            Literal::Nil => write!(f, "nil"),
         }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub token_type: TokenType,
     pub lexeme: String,
-    pub literal: Option<TokenLiteral>,
-    pub line: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub literal: Option<Literal>,
+    /// Byte offset of the lexeme in its source, resolved through the `SourceMap`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: usize,
 }
 
 impl Token {
     pub fn new(
         token_type: TokenType,
         lexeme: String,
-        literal: Option<TokenLiteral>,
-        line: usize,
+        literal: Option<Literal>,
+        offset: usize,
     ) -> Self {
         Token {
             token_type,
             lexeme,
             literal,
-            line,
+            offset,
         }
     }
 }
 
+/// Tokens are equal if they'd scan from the same text, wherever in the source
+/// they are.
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.token_type == other.token_type
+            && self.lexeme == other.lexeme
+            && self.literal == other.literal
+    }
+}
+
 impl Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{:?} {} {}",
-            self.token_type,
-            self.lexeme,
-            self.literal.clone().unwrap_or_default(),
-            // self.literal.as_ref().unwrap_or(&String::new()),
-        )
+        write!(f, "{:?} {}", self.token_type, self.lexeme)?;
+        match &self.literal {
+            Some(literal) => write!(f, " {}", literal),
+            None => Ok(()),
+        }
     }
 }
@@ -0,0 +1,47 @@
+use std::fmt::Display;
+
+use crate::{native::NativeFunction, token::Literal};
+
+/// A Lox value at runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Native(NativeFunction),
+}
+
+impl Value {
+    /// `false` and `nil` are falsey, everything else is truthy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Nil => false,
+            Value::Bool(b) => *b,
+            _ => true,
+        }
+    }
+}
+
+impl From<&Literal> for Value {
+    fn from(literal: &Literal) -> Self {
+        match literal {
+            Literal::Number(num) => Value::Number(*num),
+            Literal::Str(val) => Value::Str(val.clone()),
+            Literal::Bool(val) => Value::Bool(*val),
+            Literal::Nil => Value::Nil,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Native(native) => write!(f, "{:?}", native),
+        }
+    }
+}